@@ -0,0 +1,53 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use std::hint::black_box;
+
+use project_core::{
+    data::{Direction, Message, Timestamp},
+    MatchMode, Parser,
+};
+
+fn line(text: &str) -> Message {
+    Message::new(Timestamp::from_millis(0), Direction::In, text)
+}
+
+fn bench_parser(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parser");
+    group.throughput(Throughput::Elements(1));
+
+    let simple = Parser::new(r"(\w+): (-?\d+\.\d+)", "$1", "$2").unwrap();
+    let message = line("Temperature: 25.5");
+    group.bench_function("single_point", |b| {
+        b.iter(|| simple.parse(black_box(&message)).unwrap())
+    });
+
+    let adc = Parser::new(r"ch(\d+)=(\d+)", "\"ch\" + $1", "($2 * 3.3 / 4095) - 0.5").unwrap();
+    let message = line("ch3=2048");
+    group.bench_function("arithmetic", |b| {
+        b.iter(|| adc.parse(black_box(&message)).unwrap())
+    });
+
+    let all = Parser::new(r"(?P<k>\w+)=(?P<v>-?[\d.]+)", "${k}", "${v}")
+        .unwrap()
+        .with_match_mode(MatchMode::All);
+    let message = line("t=21.3 h=40.1 p=1013.2");
+    group.bench_function("all_matches", |b| {
+        b.iter(|| all.parse_all(black_box(&message)).unwrap())
+    });
+
+    // Reference point: what every message used to cost when the pattern
+    // and expressions were compiled per call
+    let message = line("Temperature: 25.5");
+    group.bench_function("compile_per_message", |b| {
+        b.iter(|| {
+            Parser::new(r"(\w+): (-?\d+\.\d+)", "$1", "$2")
+                .unwrap()
+                .parse(black_box(&message))
+                .unwrap()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_parser);
+criterion_main!(benches);
@@ -1,9 +1,10 @@
 // Canonical data types used across crates
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
+use std::sync::OnceLock;
 
 /// Timestamp wrapper for clarity and type-safety.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Timestamp(pub u64);
 
 impl Timestamp {
@@ -28,20 +29,24 @@ impl From<Timestamp> for u64 {
 }
 
 /// Message delivered to UI representing bytes received/sent on a port
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
     timestamp: Timestamp,
     direction: Direction,
-    /// Message payload as a UTF-8 string (display-friendly)
-    text: String,
+    /// Raw payload exactly as received from / sent to the port
+    data: Vec<u8>,
+    /// UTF-8 view of `data`, decoded on first access
+    #[serde(skip)]
+    text: OnceLock<String>,
 }
 
 impl Message {
-    pub fn new(timestamp: Timestamp, direction: Direction, text: impl Into<String>) -> Self {
+    pub fn new(timestamp: Timestamp, direction: Direction, data: impl Into<Vec<u8>>) -> Self {
         Self {
             timestamp,
             direction,
-            text: text.into(),
+            data: data.into(),
+            text: OnceLock::new(),
         }
     }
 
@@ -53,11 +58,35 @@ impl Message {
         self.direction
     }
 
+    /// Raw payload bytes
+    pub fn bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Consume the message and return the raw payload
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+
+    /// Payload decoded as UTF-8 (display-friendly). Invalid sequences are
+    /// replaced with U+FFFD; use `bytes()` when exact data matters.
     pub fn text(&self) -> &str {
-        &self.text
+        self.text
+            .get_or_init(|| String::from_utf8_lossy(&self.data).into_owned())
     }
 }
 
+impl PartialEq for Message {
+    fn eq(&self, other: &Self) -> bool {
+        // The decoded text cache is derived from `data` and not compared
+        self.timestamp == other.timestamp
+            && self.direction == other.direction
+            && self.data == other.data
+    }
+}
+
+impl Eq for Message {}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Direction {
     In,
@@ -112,16 +141,28 @@ impl Point {
 
 /// Container for serial data with circular buffer
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PointBuffer(VecDeque<Point>);
+pub struct PointBuffer {
+    points: VecDeque<Point>,
+    capacity: usize,
+}
 
 impl PointBuffer {
     pub fn new(capacity: usize) -> Self {
-        Self(VecDeque::with_capacity(capacity))
+        Self {
+            points: VecDeque::with_capacity(capacity),
+            capacity,
+        }
     }
 
-    /// Add a new data point
+    /// Add a new data point, dropping the oldest one when full
     pub fn push(&mut self, point: Point) {
-        self.0.push_back(point);
+        if self.capacity == 0 {
+            return;
+        }
+        while self.points.len() >= self.capacity {
+            self.points.pop_front();
+        }
+        self.points.push_back(point);
     }
 
     /// Add a value with an explicit timestamp
@@ -131,45 +172,65 @@ impl PointBuffer {
 
     /// Get the latest data point
     pub fn last(&self) -> Option<&Point> {
-        self.0.back()
+        self.points.back()
     }
 
     /// Clear all data points
     pub fn clear(&mut self) {
-        self.0.clear();
+        self.points.clear();
     }
 
     /// Get the number of data points
     pub fn len(&self) -> usize {
-        self.0.len()
+        self.points.len()
+    }
+
+    /// Maximum number of points retained
+    pub fn capacity(&self) -> usize {
+        self.capacity
     }
 
     /// Check if the buffer is empty
     pub fn is_empty(&self) -> bool {
-        self.0.is_empty()
+        self.points.is_empty()
     }
 
     /// Get an iterator over the data points
     pub fn iter(&self) -> impl Iterator<Item = &Point> {
-        self.0.iter()
+        self.points.iter()
+    }
+
+    /// Distinct labels in order of first appearance. Unlabelled points are
+    /// reported as `None`.
+    pub fn labels(&self) -> Vec<Option<&str>> {
+        let mut labels = Vec::new();
+        for point in &self.points {
+            let label = point.label();
+            if !labels.contains(&label) {
+                labels.push(label);
+            }
+        }
+        labels
     }
 
     /// Get an iterator over data points matching the given name
     pub fn iter_by_name<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Point> + 'a {
-        self.0
+        self.points
             .iter()
             .filter(move |p| p.label.as_deref() == Some(name))
     }
 
     /// Get a new DataBuffer containing only points with the given name
     pub fn filtered_by_name(&self, name: &str) -> Self {
-        Self(
-            self.0
+        Self {
+            points: self
+                .points
                 .iter()
                 .filter(|p| p.label.as_deref() == Some(name))
                 .cloned()
                 .collect(),
-        )
+            capacity: self.capacity,
+        }
     }
 }
 
@@ -183,6 +244,30 @@ impl Default for PointBuffer {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_message_preserves_bytes() {
+        let data = vec![0x00, 0xFF, b'o', b'k', 0xC3];
+        let message = Message::new(Timestamp(0), Direction::In, data.clone());
+        assert_eq!(message.bytes(), data.as_slice());
+        assert_eq!(message.text(), "\u{0}\u{FFFD}ok\u{FFFD}");
+        assert_eq!(message.into_bytes(), data);
+    }
+
+    #[test]
+    fn test_message_text() {
+        let message = Message::new(Timestamp(0), Direction::Out, "héllo");
+        assert_eq!(message.text(), "héllo");
+        assert_eq!(message.bytes(), "héllo".as_bytes());
+    }
+
+    #[test]
+    fn test_message_eq_ignores_decoded_cache() {
+        let a = Message::new(Timestamp(1), Direction::In, "abc");
+        let b = a.clone();
+        let _ = a.text();
+        assert_eq!(a, b);
+    }
+
     #[test]
     fn test_data_point_creation() {
         let point = Point::new(Timestamp(0), 42.0);
@@ -205,6 +290,27 @@ mod tests {
         assert_eq!(buffer.last().unwrap().value(), 2.0);
     }
 
+    #[test]
+    fn test_data_buffer_evicts_oldest() {
+        let mut buffer = PointBuffer::new(2);
+        buffer.push_value(Timestamp(1), 1.0);
+        buffer.push_value(Timestamp(2), 2.0);
+        buffer.push_value(Timestamp(3), 3.0);
+        assert_eq!(buffer.len(), 2);
+        let values: Vec<_> = buffer.iter().map(|p| p.value()).collect();
+        assert_eq!(values, vec![2.0, 3.0]);
+    }
+
+    #[test]
+    fn test_data_buffer_labels() {
+        let mut buffer = PointBuffer::new(10);
+        buffer.push(Point::new(Timestamp(1), 1.0).with_label("temp"));
+        buffer.push(Point::new(Timestamp(2), 2.0));
+        buffer.push(Point::new(Timestamp(3), 3.0).with_label("temp"));
+        buffer.push(Point::new(Timestamp(4), 4.0).with_label("volt"));
+        assert_eq!(buffer.labels(), vec![Some("temp"), None, Some("volt")]);
+    }
+
     #[test]
     fn test_data_buffer_filter_by_name() {
         let mut buffer = PointBuffer::new(10);
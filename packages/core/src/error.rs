@@ -28,6 +28,9 @@ pub enum Error {
 
     #[error("IO error: {0}")]
     IoError(String),
+
+    #[error("Timed out: {0}")]
+    Timeout(String),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
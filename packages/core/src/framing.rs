@@ -0,0 +1,130 @@
+pub mod checksum;
+pub mod cobs;
+pub mod line;
+pub mod slip;
+
+use serde::{Deserialize, Serialize};
+
+use crate::data::Message;
+use crate::Result;
+
+pub use checksum::{Checksum, ChecksumFramer};
+pub use cobs::CobsFramer;
+pub use line::{Delimiter, LineFramer};
+pub use slip::SlipFramer;
+
+/// Reassembles the arbitrary chunks returned by `SerialPort::read` into
+/// discrete frames.
+pub trait Framer {
+    /// Feed one raw chunk and return every frame it completed.
+    /// A malformed frame yields an `Err` in place of that frame; framing
+    /// continues with the following bytes.
+    fn push(&mut self, chunk: &Message) -> Vec<Result<Message>>;
+
+    /// Emit whatever partial frame is buffered, e.g. when the port closes.
+    /// A malformed one yields an `Err`, as in `push`.
+    fn flush(&mut self) -> Option<Result<Message>>;
+}
+
+impl<F: Framer + ?Sized> Framer for Box<F> {
+    fn push(&mut self, chunk: &Message) -> Vec<Result<Message>> {
+        (**self).push(chunk)
+    }
+
+    fn flush(&mut self) -> Option<Result<Message>> {
+        (**self).flush()
+    }
+}
+
+/// Serializable choice of framing, covering both directions
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "delimiter", rename_all = "snake_case")]
+pub enum Framing {
+    Line(Delimiter),
+    Cobs,
+    Slip,
+    /// Pass chunks through as read
+    Raw,
+}
+
+impl Default for Framing {
+    /// Newline-terminated text
+    fn default() -> Self {
+        Framing::Line(Delimiter::default())
+    }
+}
+
+impl Framing {
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            Framing::Line(delimiter) => delimiter.validate(),
+            Framing::Cobs | Framing::Slip | Framing::Raw => Ok(()),
+        }
+    }
+
+    /// Framer that decodes incoming bytes, or `None` for `Raw`. Fails if
+    /// the configuration is not valid.
+    pub fn framer(&self) -> Result<Option<Box<dyn Framer>>> {
+        Ok(match self {
+            Framing::Line(delimiter) => Some(Box::new(LineFramer::new(delimiter.clone())?)),
+            Framing::Cobs => Some(Box::new(CobsFramer::new())),
+            Framing::Slip => Some(Box::new(SlipFramer::new())),
+            Framing::Raw => None,
+        })
+    }
+
+    /// Wrap an outgoing payload so the other end sees it as one frame
+    pub fn encode(&self, payload: &[u8]) -> Vec<u8> {
+        match self {
+            Framing::Line(delimiter) => {
+                let mut out = payload.to_vec();
+                out.extend_from_slice(delimiter.as_bytes().unwrap_or_default());
+                out
+            }
+            Framing::Cobs => cobs::encode(payload),
+            Framing::Slip => slip::encode(payload),
+            Framing::Raw => payload.to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Direction, Timestamp};
+    use crate::Error;
+
+    #[test]
+    fn test_encoded_payload_frames_back() {
+        let payload = [b'a', 0, 0xC0, 0xDB, b'\n'];
+        for framing in [Framing::Cobs, Framing::Slip] {
+            let mut framer = framing.framer().unwrap().unwrap();
+            let chunk = Message::new(Timestamp(0), Direction::In, framing.encode(&payload));
+            let frames = framer.push(&chunk);
+            assert_eq!(frames.len(), 1, "{:?}", framing);
+            assert_eq!(frames[0].as_ref().unwrap().bytes(), &payload);
+        }
+
+        assert_eq!(Framing::default().encode(b"hi"), b"hi\n");
+        assert_eq!(Framing::Raw.encode(b"hi"), b"hi");
+        assert!(Framing::Raw.framer().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_empty_delimiter_rejected() {
+        let framing = Framing::Line(Delimiter::Bytes(Vec::new()));
+        assert!(matches!(framing.validate(), Err(Error::ConfigError(_))));
+        assert!(framing.framer().is_err());
+        assert!(Framing::Line(Delimiter::Bytes(b";".to_vec()))
+            .validate()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_serialization() {
+        let json = serde_json::to_string(&Framing::Line(Delimiter::CrLf)).unwrap();
+        assert_eq!(json, r#"{"kind":"line","delimiter":"CrLf"}"#);
+        let cobs: Framing = serde_json::from_str(r#"{"kind":"cobs"}"#).unwrap();
+        assert_eq!(cobs, Framing::Cobs);
+    }
+}
@@ -0,0 +1,323 @@
+use serde::{Deserialize, Serialize};
+
+use super::Framer;
+use crate::data::Message;
+use crate::{Error, Result};
+
+/// Integrity check carried at the end of each frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Checksum {
+    /// CRC-8 (poly 0x07, init 0x00), one trailing byte
+    Crc8,
+    /// CRC-16/CCITT-FALSE (poly 0x1021, init 0xFFFF), two trailing bytes,
+    /// big endian
+    Crc16Ccitt,
+    /// CRC-16/MODBUS (poly 0x8005 reflected, init 0xFFFF), two trailing
+    /// bytes, little endian
+    Crc16Modbus,
+    /// CRC-32 (IEEE 802.3), four trailing bytes, little endian
+    Crc32,
+    /// NMEA 0183 `*HH`: XOR of the characters between `$`/`!` and `*`
+    NmeaXor,
+}
+
+impl Checksum {
+    /// Check a frame and return its payload with the checksum removed
+    pub fn verify(self, frame: &[u8]) -> Result<Vec<u8>> {
+        if self == Checksum::NmeaXor {
+            return verify_nmea(frame);
+        }
+
+        let width = self.width();
+        if frame.len() < width {
+            return Err(Error::ParseError(format!(
+                "Frame of {} bytes is too short for a {}-byte checksum",
+                frame.len(),
+                width
+            )));
+        }
+        let (payload, trailer) = frame.split_at(frame.len() - width);
+        let received = match self {
+            Checksum::Crc8 => trailer[0] as u32,
+            Checksum::Crc16Ccitt => u16::from_be_bytes([trailer[0], trailer[1]]) as u32,
+            Checksum::Crc16Modbus => u16::from_le_bytes([trailer[0], trailer[1]]) as u32,
+            Checksum::Crc32 => u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]),
+            Checksum::NmeaXor => unreachable!(),
+        };
+        let expected = self.compute(payload);
+        if received != expected {
+            return Err(mismatch(expected, received, width));
+        }
+        Ok(payload.to_vec())
+    }
+
+    /// Append the checksum to an outgoing payload
+    pub fn append(self, payload: &[u8]) -> Vec<u8> {
+        let mut frame = payload.to_vec();
+        let value = self.compute(payload);
+        match self {
+            Checksum::Crc8 => frame.push(value as u8),
+            Checksum::Crc16Ccitt => frame.extend((value as u16).to_be_bytes()),
+            Checksum::Crc16Modbus => frame.extend((value as u16).to_le_bytes()),
+            Checksum::Crc32 => frame.extend(value.to_le_bytes()),
+            Checksum::NmeaXor => frame.extend(format!("*{:02X}", value).bytes()),
+        }
+        frame
+    }
+
+    /// Checksum of a payload
+    pub fn compute(self, payload: &[u8]) -> u32 {
+        match self {
+            Checksum::Crc8 => crc8(payload) as u32,
+            Checksum::Crc16Ccitt => crc16_ccitt(payload) as u32,
+            Checksum::Crc16Modbus => crc16_modbus(payload) as u32,
+            Checksum::Crc32 => crc32(payload),
+            Checksum::NmeaXor => nmea_xor(nmea_body(payload)) as u32,
+        }
+    }
+
+    /// Size of the binary trailer in bytes
+    fn width(self) -> usize {
+        match self {
+            Checksum::Crc8 => 1,
+            Checksum::Crc16Ccitt | Checksum::Crc16Modbus => 2,
+            Checksum::Crc32 => 4,
+            Checksum::NmeaXor => 3,
+        }
+    }
+}
+
+fn mismatch(expected: u32, received: u32, width: usize) -> Error {
+    Error::ParseError(format!(
+        "Checksum mismatch: expected 0x{:0w$X}, got 0x{:0w$X}",
+        expected,
+        received,
+        w = width * 2
+    ))
+}
+
+pub fn crc8(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |mut crc, &byte| {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+pub fn crc16_ccitt(data: &[u8]) -> u16 {
+    data.iter().fold(0xFFFFu16, |mut crc, &byte| {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+pub fn crc16_modbus(data: &[u8]) -> u16 {
+    data.iter().fold(0xFFFFu16, |mut crc, &byte| {
+        crc ^= byte as u16;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xA001
+            } else {
+                crc >> 1
+            };
+        }
+        crc
+    })
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(0xFFFF_FFFFu32, |mut crc, &byte| {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+        crc
+    })
+}
+
+pub fn nmea_xor(data: &[u8]) -> u8 {
+    data.iter().fold(0, |acc, &byte| acc ^ byte)
+}
+
+/// Sentence without its leading `$`/`!`
+fn nmea_body(sentence: &[u8]) -> &[u8] {
+    match sentence.first() {
+        Some(b'$' | b'!') => &sentence[1..],
+        _ => sentence,
+    }
+}
+
+fn verify_nmea(frame: &[u8]) -> Result<Vec<u8>> {
+    let frame = frame.trim_ascii_end();
+    let star = frame
+        .iter()
+        .rposition(|&b| b == b'*')
+        .ok_or_else(|| Error::ParseError("Missing NMEA checksum".to_string()))?;
+    let (sentence, trailer) = frame.split_at(star);
+    let received = std::str::from_utf8(&trailer[1..])
+        .ok()
+        .filter(|hex| hex.len() == 2)
+        .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        .ok_or_else(|| {
+            Error::ParseError(format!("Invalid NMEA checksum at offset {}", star + 1))
+        })?;
+    let expected = nmea_xor(nmea_body(sentence));
+    if received != expected {
+        return Err(mismatch(expected as u32, received as u32, 1));
+    }
+    Ok(sentence.to_vec())
+}
+
+/// Wraps a framer and verifies the checksum of every frame it yields,
+/// replacing bad frames with an error and stripping the checksum from good
+/// ones
+#[derive(Debug, Clone)]
+pub struct ChecksumFramer<F> {
+    inner: F,
+    checksum: Checksum,
+}
+
+impl<F: Framer> ChecksumFramer<F> {
+    pub fn new(inner: F, checksum: Checksum) -> Self {
+        Self { inner, checksum }
+    }
+
+    pub fn checksum(&self) -> Checksum {
+        self.checksum
+    }
+
+    fn check(&self, frame: Message) -> Result<Message> {
+        let payload = self.checksum.verify(frame.bytes())?;
+        Ok(Message::new(frame.timestamp(), frame.direction(), payload))
+    }
+}
+
+impl<F: Framer> Framer for ChecksumFramer<F> {
+    fn push(&mut self, chunk: &Message) -> Vec<Result<Message>> {
+        self.inner
+            .push(chunk)
+            .into_iter()
+            .map(|frame| frame.and_then(|frame| self.check(frame)))
+            .collect()
+    }
+
+    /// A trailing partial frame is still verified; a bad one is reported
+    fn flush(&mut self) -> Option<Result<Message>> {
+        self.inner
+            .flush()
+            .map(|frame| frame.and_then(|frame| self.check(frame)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Direction, Timestamp};
+    use crate::framing::{cobs, CobsFramer, LineFramer};
+
+    const CHECK: &[u8] = b"123456789";
+
+    #[test]
+    fn test_check_values() {
+        assert_eq!(crc8(CHECK), 0xF4);
+        assert_eq!(crc16_ccitt(CHECK), 0x29B1);
+        assert_eq!(crc16_modbus(CHECK), 0x4B37);
+        assert_eq!(crc32(CHECK), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_append_and_verify() {
+        for checksum in [
+            Checksum::Crc8,
+            Checksum::Crc16Ccitt,
+            Checksum::Crc16Modbus,
+            Checksum::Crc32,
+            Checksum::NmeaXor,
+        ] {
+            let frame = checksum.append(b"$GPTXT,hello");
+            assert_eq!(
+                checksum.verify(&frame).unwrap(),
+                b"$GPTXT,hello",
+                "{:?}",
+                checksum
+            );
+
+            let mut corrupted = frame.clone();
+            corrupted[3] ^= 0x01;
+            assert!(checksum.verify(&corrupted).is_err(), "{:?}", checksum);
+        }
+        // Modbus sends the low byte first
+        assert_eq!(Checksum::Crc16Modbus.append(CHECK)[9..], [0x37, 0x4B]);
+    }
+
+    #[test]
+    fn test_nmea() {
+        let sentence = b"$GPGLL,4916.45,N,12311.12,W,225444,A,*1D\r";
+        assert_eq!(
+            Checksum::NmeaXor.verify(sentence).unwrap(),
+            b"$GPGLL,4916.45,N,12311.12,W,225444,A,"
+        );
+        assert_eq!(
+            Checksum::NmeaXor.verify(b"$GPGLL,4916.45,N,12311.12,W,225444,A,*1E"),
+            Err(Error::ParseError(
+                "Checksum mismatch: expected 0x1D, got 0x1E".to_string()
+            ))
+        );
+        assert!(Checksum::NmeaXor.verify(b"$GPGLL,no,checksum").is_err());
+        assert!(Checksum::NmeaXor.verify(b"$GPGLL*Z1").is_err());
+    }
+
+    #[test]
+    fn test_framer_replaces_bad_frames() {
+        let mut framer = ChecksumFramer::new(CobsFramer::new(), Checksum::Crc16Modbus);
+        let mut bad = Checksum::Crc16Modbus.append(&[1, 2, 3]);
+        bad[0] = 9;
+        let mut stream = cobs::encode(&Checksum::Crc16Modbus.append(&[1, 2, 3]));
+        stream.extend(cobs::encode(&bad));
+
+        let frames = framer.push(&Message::new(Timestamp(4), Direction::In, stream));
+        assert_eq!(frames.len(), 2);
+        let good = frames[0].as_ref().unwrap();
+        assert_eq!(good.bytes(), &[1, 2, 3]);
+        assert_eq!(good.timestamp(), Timestamp(4));
+        assert!(frames[1].is_err());
+
+        let mut framer = ChecksumFramer::new(LineFramer::default(), Checksum::NmeaXor);
+        let mut text = Checksum::NmeaXor.append(b"$GPTXT,a");
+        text.extend(b"\n$GPTXT,b*00");
+        let frames = framer.push(&Message::new(Timestamp(0), Direction::In, text));
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].as_ref().unwrap().text(), "$GPTXT,a");
+        // The unterminated trailing frame fails verification and is
+        // reported like any other bad frame
+        assert!(matches!(framer.flush(), Some(Err(Error::ParseError(_)))));
+        assert!(framer.flush().is_none());
+
+        let mut good = Checksum::NmeaXor.append(b"$GPTXT,c");
+        good.splice(
+            0..0,
+            *b"
+",
+        );
+        framer.push(&Message::new(Timestamp(1), Direction::In, good));
+        assert_eq!(framer.flush().unwrap().unwrap().text(), "$GPTXT,c");
+    }
+}
@@ -0,0 +1,260 @@
+use super::Framer;
+use crate::data::{Direction, Message, Timestamp};
+use crate::{Error, Result};
+
+/// Encode `payload` with Consistent Overhead Byte Stuffing, including the
+/// trailing `0x00` delimiter
+pub fn encode(payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(payload.len() + payload.len() / 254 + 2);
+    let mut code_index = 0;
+    let mut code = 1u8;
+    out.push(0);
+
+    for &byte in payload {
+        if byte != 0 {
+            out.push(byte);
+            code += 1;
+        }
+        if byte == 0 || code == 0xFF {
+            out[code_index] = code;
+            code_index = out.len();
+            out.push(0);
+            code = 1;
+        }
+    }
+    out[code_index] = code;
+    out.push(0);
+    out
+}
+
+/// Decode one COBS frame, without its `0x00` delimiter
+pub fn decode(frame: &[u8]) -> Result<Vec<u8>> {
+    decode_at(frame, 0)
+}
+
+/// Decode a frame that starts at stream offset `base`, so errors report
+/// the offset of the offending byte in the stream
+fn decode_at(frame: &[u8], base: usize) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(frame.len());
+    let mut i = 0;
+    while i < frame.len() {
+        let code = frame[i] as usize;
+        if code == 0 {
+            return Err(Error::ParseError(format!(
+                "Invalid COBS frame: zero code byte at offset {}",
+                base + i
+            )));
+        }
+        let end = i + code;
+        if end > frame.len() {
+            return Err(Error::ParseError(format!(
+                "Invalid COBS frame: block at offset {} needs {} bytes, {} left",
+                base + i,
+                code - 1,
+                frame.len() - i - 1
+            )));
+        }
+        if let Some(zero) = frame[i + 1..end].iter().position(|&b| b == 0) {
+            return Err(Error::ParseError(format!(
+                "Invalid COBS frame: zero byte at offset {}",
+                base + i + 1 + zero
+            )));
+        }
+        out.extend_from_slice(&frame[i + 1..end]);
+        i = end;
+        if code < 0xFF && i < frame.len() {
+            out.push(0);
+        }
+    }
+    Ok(out)
+}
+
+/// Splits a byte stream on `0x00` and COBS-decodes each frame. Frames are
+/// timestamped at their first byte; empty frames are dropped.
+#[derive(Debug, Clone)]
+pub struct CobsFramer {
+    max_len: usize,
+    buffer: Vec<u8>,
+    start: Option<Timestamp>,
+    /// Stream offset of the first buffered byte
+    offset: usize,
+    /// Bytes consumed so far
+    position: usize,
+    /// The current frame overflowed and is being skipped
+    discarding: bool,
+    direction: Direction,
+}
+
+impl CobsFramer {
+    /// Default upper bound on a single encoded frame
+    pub const DEFAULT_MAX_LEN: usize = 64 * 1024;
+
+    pub fn new() -> Self {
+        Self {
+            max_len: Self::DEFAULT_MAX_LEN,
+            buffer: Vec::new(),
+            start: None,
+            offset: 0,
+            position: 0,
+            discarding: false,
+            direction: Direction::In,
+        }
+    }
+
+    /// Frames growing beyond `max_len` encoded bytes are discarded with an
+    /// error
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+
+    fn take_frame(&mut self) -> Option<Result<Message>> {
+        let start = self.start.take()?;
+        let data = std::mem::take(&mut self.buffer);
+        if data.is_empty() {
+            return None;
+        }
+        Some(decode_at(&data, self.offset).map(|d| Message::new(start, self.direction, d)))
+    }
+}
+
+impl Default for CobsFramer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Framer for CobsFramer {
+    fn push(&mut self, chunk: &Message) -> Vec<Result<Message>> {
+        let mut frames = Vec::new();
+        self.direction = chunk.direction();
+
+        for &byte in chunk.bytes() {
+            let position = self.position;
+            self.position += 1;
+
+            if byte == 0 {
+                if !std::mem::take(&mut self.discarding) {
+                    frames.extend(self.take_frame());
+                }
+                continue;
+            }
+            if self.discarding {
+                continue;
+            }
+            if self.start.is_none() {
+                self.start = Some(chunk.timestamp());
+                self.offset = position;
+            }
+            self.buffer.push(byte);
+
+            if self.buffer.len() > self.max_len {
+                self.buffer.clear();
+                self.start = None;
+                self.discarding = true;
+                frames.push(Err(Error::ParseError(format!(
+                    "COBS frame at offset {} exceeds maximum length of {} bytes",
+                    self.offset, self.max_len
+                ))));
+            }
+        }
+
+        frames
+    }
+
+    /// A partial COBS frame cannot be decoded reliably, so it is dropped
+    fn flush(&mut self) -> Option<Result<Message>> {
+        self.buffer.clear();
+        self.start = None;
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(ts: u64, data: &[u8]) -> Message {
+        Message::new(Timestamp(ts), Direction::In, data)
+    }
+
+    #[test]
+    fn test_encode_known_vectors() {
+        assert_eq!(encode(&[]), vec![0x01, 0x00]);
+        assert_eq!(encode(&[0x00]), vec![0x01, 0x01, 0x00]);
+        assert_eq!(
+            encode(&[0x11, 0x22, 0x00, 0x33]),
+            vec![0x03, 0x11, 0x22, 0x02, 0x33, 0x00]
+        );
+        assert_eq!(
+            encode(&[0x11, 0x00, 0x00]),
+            vec![0x02, 0x11, 0x01, 0x01, 0x00]
+        );
+    }
+
+    #[test]
+    fn test_round_trip_long_runs() {
+        for len in [253, 254, 255, 600] {
+            let payload: Vec<u8> = (0..len).map(|i| (i % 255 + 1) as u8).collect();
+            let encoded = encode(&payload);
+            assert!(!encoded[..encoded.len() - 1].contains(&0));
+            assert_eq!(decode(&encoded[..encoded.len() - 1]).unwrap(), payload);
+        }
+    }
+
+    #[test]
+    fn test_decode_errors_report_offset() {
+        let err = decode(&[0x05, 0x11, 0x22]).unwrap_err();
+        assert_eq!(
+            err,
+            Error::ParseError(
+                "Invalid COBS frame: block at offset 0 needs 4 bytes, 2 left".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_framer_splits_stream() {
+        let mut framer = CobsFramer::new();
+        let mut stream = encode(&[1, 0, 2]);
+        stream.extend(encode(&[3]));
+
+        assert!(framer.push(&chunk(10, &stream[..2])).is_empty());
+        let frames = framer.push(&chunk(20, &stream[2..]));
+        assert_eq!(frames.len(), 2);
+        let first = frames[0].as_ref().unwrap();
+        assert_eq!(first.bytes(), &[1, 0, 2]);
+        assert_eq!(first.timestamp(), Timestamp(10));
+        assert_eq!(frames[1].as_ref().unwrap().bytes(), &[3]);
+    }
+
+    #[test]
+    fn test_framer_error_uses_stream_offset() {
+        let mut framer = CobsFramer::new();
+        let mut stream = encode(&[1, 2]);
+        stream.extend([0x04, 0x01, 0x00]);
+        stream.extend(encode(&[9]));
+
+        let frames = framer.push(&chunk(0, &stream));
+        assert_eq!(frames.len(), 3);
+        assert_eq!(
+            frames[1],
+            Err(Error::ParseError(
+                "Invalid COBS frame: block at offset 4 needs 3 bytes, 1 left".to_string()
+            ))
+        );
+        assert_eq!(frames[2].as_ref().unwrap().bytes(), &[9]);
+    }
+
+    #[test]
+    fn test_framer_max_len() {
+        let mut framer = CobsFramer::new().with_max_len(3);
+        let mut stream = encode(&[1, 2, 3, 4, 5]);
+        stream.extend(encode(&[6]));
+        let frames = framer.push(&chunk(0, &stream));
+        assert_eq!(frames.len(), 2);
+        assert!(frames[0].is_err());
+        assert_eq!(frames[1].as_ref().unwrap().bytes(), &[6]);
+        assert!(framer.flush().is_none());
+    }
+}
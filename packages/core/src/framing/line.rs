@@ -0,0 +1,323 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::Framer;
+use crate::data::{Direction, Message, Timestamp};
+use crate::{Error, Result};
+
+/// Where one frame ends and the next begins
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum Delimiter {
+    /// `\n`
+    #[default]
+    Lf,
+    /// `\r\n`
+    CrLf,
+    /// `\r`
+    Cr,
+    /// Arbitrary byte sequence
+    Bytes(Vec<u8>),
+    /// A gap of at least this long between chunks ends the frame
+    IdleTimeout(Duration),
+}
+
+impl Delimiter {
+    /// Byte sequence terminating a frame, if this is a byte delimiter
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Delimiter::Lf => Some(b"\n"),
+            Delimiter::CrLf => Some(b"\r\n"),
+            Delimiter::Cr => Some(b"\r"),
+            Delimiter::Bytes(bytes) => Some(bytes),
+            Delimiter::IdleTimeout(_) => None,
+        }
+    }
+
+    /// An empty byte delimiter never matches, so nothing would be split
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            Delimiter::Bytes(bytes) if bytes.is_empty() => Err(Error::ConfigError(
+                "Delimiter bytes must not be empty".to_string(),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Splits a byte stream on a delimiter. Each emitted `Message` carries the
+/// timestamp of the chunk that delivered its first byte, and the delimiter
+/// itself is stripped. Empty frames are dropped.
+#[derive(Debug, Clone)]
+pub struct LineFramer {
+    delimiter: Delimiter,
+    max_len: usize,
+    buffer: Vec<u8>,
+    /// Timestamp of the first buffered byte
+    start: Option<Timestamp>,
+    /// Timestamp of the last chunk seen, for idle detection
+    last: Option<Timestamp>,
+    /// The current frame overflowed and is being skipped up to the next
+    /// delimiter; `buffer` then only holds enough bytes to spot it
+    discarding: bool,
+    direction: Direction,
+}
+
+impl LineFramer {
+    /// Default upper bound on a single frame
+    pub const DEFAULT_MAX_LEN: usize = 64 * 1024;
+
+    /// Fails if `delimiter` is not valid
+    pub fn new(delimiter: Delimiter) -> Result<Self> {
+        delimiter.validate()?;
+        Ok(Self::with_valid_delimiter(delimiter))
+    }
+
+    fn with_valid_delimiter(delimiter: Delimiter) -> Self {
+        Self {
+            delimiter,
+            max_len: Self::DEFAULT_MAX_LEN,
+            buffer: Vec::new(),
+            start: None,
+            last: None,
+            discarding: false,
+            direction: Direction::In,
+        }
+    }
+
+    /// Frames growing beyond `max_len` bytes are reported as an error and
+    /// skipped up to the next delimiter
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+
+    pub fn delimiter(&self) -> &Delimiter {
+        &self.delimiter
+    }
+
+    /// Emit the buffered frame if the idle timeout has elapsed at `now`.
+    /// Only meaningful with `Delimiter::IdleTimeout`; callers with a clock
+    /// can use this to end the last frame without waiting for more input.
+    pub fn poll_idle(&mut self, now: Timestamp) -> Option<Message> {
+        if self.is_idle(now) {
+            self.take_frame()
+        } else {
+            None
+        }
+    }
+
+    fn is_idle(&self, now: Timestamp) -> bool {
+        let Delimiter::IdleTimeout(timeout) = self.delimiter else {
+            return false;
+        };
+        match self.last {
+            Some(last) => {
+                now.as_millis().saturating_sub(last.as_millis()) >= timeout.as_millis() as u64
+            }
+            None => false,
+        }
+    }
+
+    fn take_frame(&mut self) -> Option<Message> {
+        if std::mem::take(&mut self.discarding) {
+            self.buffer.clear();
+            return None;
+        }
+        let start = self.start.take()?;
+        let data = std::mem::take(&mut self.buffer);
+        if data.is_empty() {
+            return None;
+        }
+        Some(Message::new(start, self.direction, data))
+    }
+}
+
+impl Default for LineFramer {
+    fn default() -> Self {
+        Self::with_valid_delimiter(Delimiter::default())
+    }
+}
+
+impl Framer for LineFramer {
+    fn push(&mut self, chunk: &Message) -> Vec<Result<Message>> {
+        let mut frames = Vec::new();
+
+        if self.is_idle(chunk.timestamp()) {
+            frames.extend(self.take_frame().map(Ok));
+        }
+        self.last = Some(chunk.timestamp());
+        self.direction = chunk.direction();
+
+        for &byte in chunk.bytes() {
+            if self.start.is_none() && !self.discarding {
+                self.start = Some(chunk.timestamp());
+            }
+            self.buffer.push(byte);
+
+            if let Some(delimiter) = self.delimiter.as_bytes() {
+                if !delimiter.is_empty() && self.buffer.ends_with(delimiter) {
+                    self.buffer.truncate(self.buffer.len() - delimiter.len());
+                    frames.extend(self.take_frame().map(Ok));
+                    continue;
+                }
+                if self.discarding {
+                    // Keep just enough to recognise a delimiter split
+                    // across bytes
+                    let keep = delimiter.len().saturating_sub(1);
+                    let excess = self.buffer.len().saturating_sub(keep);
+                    self.buffer.drain(..excess);
+                    continue;
+                }
+            } else if self.discarding {
+                self.buffer.clear();
+                continue;
+            }
+
+            // A partial delimiter at the end may still complete the frame
+            let pending = self
+                .delimiter
+                .as_bytes()
+                .map_or(0, |delimiter| delimiter.len().saturating_sub(1));
+            if self.buffer.len() > self.max_len + pending {
+                self.buffer.clear();
+                self.start = None;
+                self.discarding = true;
+                frames.push(Err(Error::ParseError(format!(
+                    "Frame exceeds maximum length of {} bytes",
+                    self.max_len
+                ))));
+            }
+        }
+
+        frames
+    }
+
+    fn flush(&mut self) -> Option<Result<Message>> {
+        self.take_frame().map(Ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(ts: u64, data: &str) -> Message {
+        Message::new(Timestamp(ts), Direction::In, data)
+    }
+
+    fn texts(frames: Vec<Result<Message>>) -> Vec<String> {
+        frames
+            .into_iter()
+            .map(|f| f.unwrap().text().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_reassembles_split_line() {
+        let mut framer = LineFramer::new(Delimiter::Lf).unwrap();
+        assert!(framer.push(&chunk(10, "Temp: 2")).is_empty());
+        let frames = framer.push(&chunk(20, "5.5\nHum"));
+        assert_eq!(frames.len(), 1);
+        let frame = frames[0].as_ref().unwrap();
+        assert_eq!(frame.text(), "Temp: 25.5");
+        // Timestamped at the first byte of the frame
+        assert_eq!(frame.timestamp(), Timestamp(10));
+
+        let frames = framer.push(&chunk(30, "idity: 40\n"));
+        assert_eq!(frames[0].as_ref().unwrap().timestamp(), Timestamp(20));
+        assert_eq!(texts(frames), vec!["Humidity: 40"]);
+    }
+
+    #[test]
+    fn test_multiple_lines_in_one_chunk() {
+        let mut framer = LineFramer::new(Delimiter::Lf).unwrap();
+        let frames = framer.push(&chunk(0, "a=1\nb=2\n\nc="));
+        assert_eq!(texts(frames), vec!["a=1", "b=2"]);
+        assert_eq!(framer.flush().unwrap().unwrap().text(), "c=");
+        assert!(framer.flush().is_none());
+    }
+
+    #[test]
+    fn test_crlf_split_across_chunks() {
+        let mut framer = LineFramer::new(Delimiter::CrLf).unwrap();
+        assert!(framer.push(&chunk(0, "one\r")).is_empty());
+        let frames = framer.push(&chunk(1, "\ntwo\r\n"));
+        assert_eq!(texts(frames), vec!["one", "two"]);
+    }
+
+    #[test]
+    fn test_cr_and_custom_delimiters() {
+        let mut framer = LineFramer::new(Delimiter::Cr).unwrap();
+        assert_eq!(texts(framer.push(&chunk(0, "x\ry\r"))), vec!["x", "y"]);
+
+        let mut framer = LineFramer::new(Delimiter::Bytes(b"||".to_vec())).unwrap();
+        assert_eq!(
+            texts(framer.push(&chunk(0, "a|b||c|"))),
+            vec!["a|b".to_string()]
+        );
+        assert_eq!(texts(framer.push(&chunk(1, "|"))), vec!["c"]);
+    }
+
+    #[test]
+    fn test_empty_delimiter_rejected() {
+        assert!(matches!(
+            LineFramer::new(Delimiter::Bytes(Vec::new())),
+            Err(Error::ConfigError(_))
+        ));
+    }
+
+    #[test]
+    fn test_idle_timeout() {
+        let mut framer =
+            LineFramer::new(Delimiter::IdleTimeout(Duration::from_millis(50))).unwrap();
+        assert!(framer.push(&chunk(0, "abc")).is_empty());
+        assert!(framer.push(&chunk(20, "def")).is_empty());
+        assert!(framer.poll_idle(Timestamp(60)).is_none());
+
+        let frames = framer.push(&chunk(100, "ghi"));
+        assert_eq!(frames[0].as_ref().unwrap().timestamp(), Timestamp(0));
+        assert_eq!(texts(frames), vec!["abcdef"]);
+
+        let frame = framer.poll_idle(Timestamp(150)).unwrap();
+        assert_eq!(frame.text(), "ghi");
+        assert_eq!(frame.timestamp(), Timestamp(100));
+    }
+
+    #[test]
+    fn test_max_len() {
+        let mut framer = LineFramer::new(Delimiter::Lf).unwrap().with_max_len(4);
+        let frames = framer.push(&chunk(0, "toolong\nok\n"));
+        assert_eq!(frames.len(), 2);
+        assert!(matches!(frames[0], Err(Error::ParseError(_))));
+        assert_eq!(frames[1].as_ref().unwrap().text(), "ok");
+
+        // The overlong line is skipped across chunks, and a multi-byte
+        // delimiter is still found when split between them
+        let mut framer = LineFramer::new(Delimiter::CrLf).unwrap().with_max_len(4);
+        let frames = framer.push(&chunk(0, "abcdefgh\r"));
+        assert_eq!(frames.len(), 1);
+        assert!(frames[0].is_err());
+        assert!(framer.push(&chunk(1, "ij")).is_empty());
+        assert!(framer.push(&chunk(2, "\r")).is_empty());
+        assert_eq!(texts(framer.push(&chunk(3, "\nnext\r\n"))), vec!["next"]);
+        assert!(framer.flush().is_none());
+
+        // An overlong frame is discarded on flush rather than emitted
+        let mut framer = LineFramer::new(Delimiter::Lf).unwrap().with_max_len(2);
+        assert_eq!(framer.push(&chunk(0, "abcdef")).len(), 1);
+        assert!(framer.flush().is_none());
+        assert_eq!(texts(framer.push(&chunk(1, "ok\n"))), vec!["ok"]);
+    }
+
+    #[test]
+    fn test_binary_payload_preserved() {
+        let mut framer = LineFramer::new(Delimiter::Lf).unwrap();
+        let frames = framer.push(&Message::new(
+            Timestamp(0),
+            Direction::In,
+            vec![0xFF, 0x00, b'\n'],
+        ));
+        assert_eq!(frames[0].as_ref().unwrap().bytes(), &[0xFF, 0x00]);
+    }
+}
@@ -0,0 +1,256 @@
+use super::Framer;
+use crate::data::{Direction, Message, Timestamp};
+use crate::{Error, Result};
+
+/// Frame delimiter
+pub const END: u8 = 0xC0;
+/// Escape byte
+pub const ESC: u8 = 0xDB;
+/// `ESC ESC_END` stands for a literal `END`
+pub const ESC_END: u8 = 0xDC;
+/// `ESC ESC_ESC` stands for a literal `ESC`
+pub const ESC_ESC: u8 = 0xDD;
+
+/// Encode `payload` as a SLIP (RFC 1055) frame. A leading `END` flushes any
+/// line noise the receiver has buffered.
+pub fn encode(payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(payload.len() + 2);
+    out.push(END);
+    for &byte in payload {
+        match byte {
+            END => out.extend([ESC, ESC_END]),
+            ESC => out.extend([ESC, ESC_ESC]),
+            b => out.push(b),
+        }
+    }
+    out.push(END);
+    out
+}
+
+/// Decode one SLIP frame, without its `END` delimiters
+pub fn decode(frame: &[u8]) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(frame.len());
+    let mut bytes = frame.iter().enumerate();
+    while let Some((i, &byte)) = bytes.next() {
+        match byte {
+            ESC => match bytes.next() {
+                Some((_, &ESC_END)) => out.push(END),
+                Some((_, &ESC_ESC)) => out.push(ESC),
+                Some((j, &other)) => return Err(invalid_escape(other, j)),
+                None => {
+                    return Err(Error::ParseError(format!(
+                        "Invalid SLIP frame: trailing escape at offset {}",
+                        i
+                    )))
+                }
+            },
+            END => {
+                return Err(Error::ParseError(format!(
+                    "Invalid SLIP frame: END byte at offset {}",
+                    i
+                )))
+            }
+            b => out.push(b),
+        }
+    }
+    Ok(out)
+}
+
+fn invalid_escape(byte: u8, offset: usize) -> Error {
+    Error::ParseError(format!(
+        "Invalid SLIP escape 0x{:02X} at offset {}",
+        byte, offset
+    ))
+}
+
+/// Splits a byte stream on SLIP `END` bytes and unescapes each frame. Frames
+/// are timestamped at their first byte; empty frames are dropped.
+#[derive(Debug, Clone)]
+pub struct SlipFramer {
+    max_len: usize,
+    buffer: Vec<u8>,
+    start: Option<Timestamp>,
+    /// The previous byte was `ESC`
+    escaped: bool,
+    /// Error found in the current frame, reported when it ends
+    error: Option<Error>,
+    /// Bytes consumed so far
+    position: usize,
+    direction: Direction,
+}
+
+impl SlipFramer {
+    /// Default upper bound on a single decoded frame
+    pub const DEFAULT_MAX_LEN: usize = 64 * 1024;
+
+    pub fn new() -> Self {
+        Self {
+            max_len: Self::DEFAULT_MAX_LEN,
+            buffer: Vec::new(),
+            start: None,
+            escaped: false,
+            error: None,
+            position: 0,
+            direction: Direction::In,
+        }
+    }
+
+    /// Frames growing beyond `max_len` bytes are discarded with an error
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+
+    fn take_frame(&mut self) -> Option<Result<Message>> {
+        self.escaped = false;
+        let start = self.start.take();
+        let data = std::mem::take(&mut self.buffer);
+        if let Some(error) = self.error.take() {
+            return Some(Err(error));
+        }
+        if data.is_empty() {
+            return None;
+        }
+        start.map(|start| Ok(Message::new(start, self.direction, data)))
+    }
+
+    fn fail(&mut self, error: Error) {
+        self.buffer.clear();
+        self.error.get_or_insert(error);
+    }
+}
+
+impl Default for SlipFramer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Framer for SlipFramer {
+    fn push(&mut self, chunk: &Message) -> Vec<Result<Message>> {
+        let mut frames = Vec::new();
+        self.direction = chunk.direction();
+
+        for &byte in chunk.bytes() {
+            let position = self.position;
+            self.position += 1;
+
+            if byte == END {
+                frames.extend(self.take_frame());
+                continue;
+            }
+            if self.start.is_none() {
+                self.start = Some(chunk.timestamp());
+            }
+            if self.error.is_some() {
+                continue;
+            }
+
+            if std::mem::take(&mut self.escaped) {
+                match byte {
+                    ESC_END => self.buffer.push(END),
+                    ESC_ESC => self.buffer.push(ESC),
+                    other => self.fail(invalid_escape(other, position)),
+                }
+            } else if byte == ESC {
+                self.escaped = true;
+            } else {
+                self.buffer.push(byte);
+            }
+
+            if self.buffer.len() > self.max_len {
+                let max_len = self.max_len;
+                self.fail(Error::ParseError(format!(
+                    "SLIP frame ending at offset {} exceeds maximum length of {} bytes",
+                    position, max_len
+                )));
+            }
+        }
+
+        frames
+    }
+
+    /// A frame is only complete at its `END`, so a partial one is dropped
+    fn flush(&mut self) -> Option<Result<Message>> {
+        self.buffer.clear();
+        self.start = None;
+        self.error = None;
+        self.escaped = false;
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(ts: u64, data: &[u8]) -> Message {
+        Message::new(Timestamp(ts), Direction::In, data)
+    }
+
+    #[test]
+    fn test_encode_escapes() {
+        assert_eq!(
+            encode(&[1, END, ESC, 2]),
+            vec![END, 1, ESC, ESC_END, ESC, ESC_ESC, 2, END]
+        );
+        let encoded = encode(&[END, 0, ESC]);
+        assert_eq!(
+            decode(&encoded[1..encoded.len() - 1]).unwrap(),
+            vec![END, 0, ESC]
+        );
+    }
+
+    #[test]
+    fn test_decode_errors_report_offset() {
+        assert_eq!(
+            decode(&[1, ESC, 0x42]),
+            Err(Error::ParseError(
+                "Invalid SLIP escape 0x42 at offset 2".to_string()
+            ))
+        );
+        assert!(decode(&[1, ESC]).is_err());
+    }
+
+    #[test]
+    fn test_framer_splits_stream() {
+        let mut framer = SlipFramer::new();
+        let mut stream = encode(&[1, END, 2]);
+        stream.extend(encode(&[3]));
+
+        // Escape sequence split across chunks
+        assert!(framer.push(&chunk(5, &stream[..3])).is_empty());
+        let frames = framer.push(&chunk(6, &stream[3..]));
+        assert_eq!(frames.len(), 2);
+        let first = frames[0].as_ref().unwrap();
+        assert_eq!(first.bytes(), &[1, END, 2]);
+        assert_eq!(first.timestamp(), Timestamp(5));
+        assert_eq!(frames[1].as_ref().unwrap().bytes(), &[3]);
+    }
+
+    #[test]
+    fn test_framer_recovers_after_bad_escape() {
+        let mut framer = SlipFramer::new();
+        let mut stream = vec![END, 7, ESC, 0x01, 8, END];
+        stream.extend(encode(&[9]));
+
+        let frames = framer.push(&chunk(0, &stream));
+        assert_eq!(frames.len(), 2);
+        assert_eq!(
+            frames[0],
+            Err(Error::ParseError(
+                "Invalid SLIP escape 0x01 at offset 3".to_string()
+            ))
+        );
+        assert_eq!(frames[1].as_ref().unwrap().bytes(), &[9]);
+    }
+
+    #[test]
+    fn test_framer_max_len() {
+        let mut framer = SlipFramer::new().with_max_len(2);
+        let frames = framer.push(&chunk(0, &encode(&[1, 2, 3])));
+        assert_eq!(frames.len(), 1);
+        assert!(frames[0].is_err());
+        assert!(framer.flush().is_none());
+    }
+}
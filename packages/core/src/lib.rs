@@ -1,10 +1,16 @@
 mod error;
-mod parser;
 mod system;
 
 pub mod data;
+pub mod framing;
+pub mod modbus;
+pub mod parser;
 pub mod serial;
+pub mod session;
 
 pub use error::{Error, Result};
-pub use parser::Parser;
+pub use parser::{
+    BinaryParser, DelimitedParser, JsonParser, MatchMode, NmeaParser, Parse, Parser, ParserConfig,
+    Pipeline,
+};
 pub use system::TimeSource;
@@ -0,0 +1,791 @@
+pub mod poll;
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use crate::data::{Direction, Message, Timestamp};
+use crate::framing::checksum::crc16_modbus;
+use crate::serial::{Parity, PortConfig, SerialPort};
+use crate::session::{read_next, ReadFuture};
+use crate::system::TimeSource;
+use crate::{Error, Result};
+
+pub use poll::{PollItem, Poller, RegisterTable, ValueFormat};
+
+/// Unit id addressing every slave; only writes may be broadcast and no
+/// slave answers them
+pub const BROADCAST: u8 = 0;
+
+/// A Modbus request PDU
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Request {
+    ReadCoils { address: u16, count: u16 },
+    ReadDiscreteInputs { address: u16, count: u16 },
+    ReadHoldingRegisters { address: u16, count: u16 },
+    ReadInputRegisters { address: u16, count: u16 },
+    WriteSingleRegister { address: u16, value: u16 },
+    WriteMultipleRegisters { address: u16, values: Vec<u16> },
+}
+
+/// Decoded response to a `Request`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Response {
+    /// Coil or discrete input states, one per requested address
+    Bits(Vec<bool>),
+    /// Register values, one per requested address
+    Registers(Vec<u16>),
+    /// The write was acknowledged (or broadcast)
+    Written,
+}
+
+impl Request {
+    pub fn function_code(&self) -> u8 {
+        match self {
+            Request::ReadCoils { .. } => 0x01,
+            Request::ReadDiscreteInputs { .. } => 0x02,
+            Request::ReadHoldingRegisters { .. } => 0x03,
+            Request::ReadInputRegisters { .. } => 0x04,
+            Request::WriteSingleRegister { .. } => 0x06,
+            Request::WriteMultipleRegisters { .. } => 0x10,
+        }
+    }
+
+    pub fn is_write(&self) -> bool {
+        matches!(
+            self,
+            Request::WriteSingleRegister { .. } | Request::WriteMultipleRegisters { .. }
+        )
+    }
+
+    /// Check the quantity limits of the Modbus specification
+    pub fn validate(&self) -> Result<()> {
+        let (count, max) = match self {
+            Request::ReadCoils { count, .. } | Request::ReadDiscreteInputs { count, .. } => {
+                (*count as usize, 2000)
+            }
+            Request::ReadHoldingRegisters { count, .. }
+            | Request::ReadInputRegisters { count, .. } => (*count as usize, 125),
+            Request::WriteSingleRegister { .. } => (1, 1),
+            Request::WriteMultipleRegisters { values, .. } => (values.len(), 123),
+        };
+        if count == 0 || count > max {
+            return Err(Error::ConfigError(format!(
+                "Modbus function 0x{:02X} needs a quantity of 1 to {}, got {}",
+                self.function_code(),
+                max,
+                count
+            )));
+        }
+        Ok(())
+    }
+
+    /// Full RTU frame: unit id, PDU and CRC-16 (low byte first)
+    pub fn encode(&self, unit: u8) -> Vec<u8> {
+        let mut frame = vec![unit, self.function_code()];
+        match self {
+            Request::ReadCoils { address, count }
+            | Request::ReadDiscreteInputs { address, count }
+            | Request::ReadHoldingRegisters { address, count }
+            | Request::ReadInputRegisters { address, count } => {
+                frame.extend(address.to_be_bytes());
+                frame.extend(count.to_be_bytes());
+            }
+            Request::WriteSingleRegister { address, value } => {
+                frame.extend(address.to_be_bytes());
+                frame.extend(value.to_be_bytes());
+            }
+            Request::WriteMultipleRegisters { address, values } => {
+                frame.extend(address.to_be_bytes());
+                frame.extend((values.len() as u16).to_be_bytes());
+                frame.push((values.len() * 2) as u8);
+                for value in values {
+                    frame.extend(value.to_be_bytes());
+                }
+            }
+        }
+        let crc = crc16_modbus(&frame);
+        frame.extend(crc.to_le_bytes());
+        frame
+    }
+
+    /// Length of the complete response frame, once enough of it has
+    /// arrived to tell
+    pub fn response_len(&self, received: &[u8]) -> Option<usize> {
+        let function = *received.get(1)?;
+        if function & 0x80 != 0 {
+            return Some(5);
+        }
+        if self.is_write() {
+            return Some(8);
+        }
+        received
+            .get(2)
+            .map(|&byte_count| 3 + byte_count as usize + 2)
+    }
+
+    /// Decode and check a complete response frame from `unit`
+    pub fn decode_response(&self, unit: u8, frame: &[u8]) -> Result<Response> {
+        if frame.len() < 5 {
+            return Err(Error::ParseError(format!(
+                "Modbus response of {} bytes is too short",
+                frame.len()
+            )));
+        }
+        let (body, crc) = frame.split_at(frame.len() - 2);
+        let expected = crc16_modbus(body);
+        let received = u16::from_le_bytes([crc[0], crc[1]]);
+        if expected != received {
+            return Err(Error::ParseError(format!(
+                "Modbus CRC mismatch: expected 0x{:04X}, got 0x{:04X}",
+                expected, received
+            )));
+        }
+        if body[0] != unit {
+            return Err(Error::ParseError(format!(
+                "Modbus response from unit {}, expected {}",
+                body[0], unit
+            )));
+        }
+
+        let function = body[1];
+        if function == self.function_code() | 0x80 {
+            let message = format!(
+                "Modbus exception 0x{:02X} ({}) from unit {}",
+                body[2],
+                exception_name(body[2]),
+                unit
+            );
+            return Err(if self.is_write() {
+                Error::WriteError(message)
+            } else {
+                Error::ReadError(message)
+            });
+        }
+        if function != self.function_code() {
+            return Err(Error::ParseError(format!(
+                "Modbus response has function 0x{:02X}, expected 0x{:02X}",
+                function,
+                self.function_code()
+            )));
+        }
+
+        match self {
+            Request::ReadCoils { count, .. } | Request::ReadDiscreteInputs { count, .. } => {
+                let data = read_payload(body, (*count as usize).div_ceil(8))?;
+                Ok(Response::Bits(
+                    (0..*count as usize)
+                        .map(|i| data[i / 8] & (1 << (i % 8)) != 0)
+                        .collect(),
+                ))
+            }
+            Request::ReadHoldingRegisters { count, .. }
+            | Request::ReadInputRegisters { count, .. } => {
+                let data = read_payload(body, *count as usize * 2)?;
+                Ok(Response::Registers(
+                    data.as_chunks::<2>()
+                        .0
+                        .iter()
+                        .map(|word| u16::from_be_bytes(*word))
+                        .collect(),
+                ))
+            }
+            Request::WriteSingleRegister { .. } | Request::WriteMultipleRegisters { .. } => {
+                let request = self.encode(unit);
+                // Both echo the address and the value / quantity
+                if body.len() != 6 || body[2..6] != request[2..6] {
+                    return Err(Error::ParseError(
+                        "Modbus write acknowledgement does not match the request".to_string(),
+                    ));
+                }
+                Ok(Response::Written)
+            }
+        }
+    }
+}
+
+/// Data bytes of a read response, checking its byte count
+fn read_payload(body: &[u8], expected: usize) -> Result<&[u8]> {
+    let byte_count = body[2] as usize;
+    if byte_count != expected || body.len() != 3 + byte_count {
+        return Err(Error::ParseError(format!(
+            "Modbus response carries {} data bytes, expected {}",
+            byte_count, expected
+        )));
+    }
+    Ok(&body[3..])
+}
+
+fn exception_name(code: u8) -> &'static str {
+    match code {
+        0x01 => "illegal function",
+        0x02 => "illegal data address",
+        0x03 => "illegal data value",
+        0x04 => "slave device failure",
+        0x05 => "acknowledge",
+        0x06 => "slave device busy",
+        0x0B => "gateway target failed to respond",
+        _ => "unknown exception",
+    }
+}
+
+/// Minimum bus silence between RTU frames: 3.5 character times, or the
+/// fixed 1.75 ms the specification recommends above 19200 baud
+pub fn frame_delay(config: &PortConfig) -> Duration {
+    if config.baud_rate == 0 || config.baud_rate > 19200 {
+        return Duration::from_micros(1750);
+    }
+    let parity_bits = if config.parity == Parity::None { 0 } else { 1 };
+    let bits_per_char = 1 + config.data_bits as u64 + parity_bits + config.stop_bits as u64;
+    Duration::from_micros(bits_per_char * 3_500_000 / config.baud_rate as u64)
+}
+
+/// Modbus RTU client driving a `SerialPort` directly.
+///
+/// The master needs the port to itself, so it must not be shared with a
+/// running `PortSession`. Requests are separated by the inter-frame delay
+/// derived from the port's `PortConfig`, and responses must arrive within
+/// the response timeout.
+///
+/// Like `PortSession`, the master reads on a clone of the port and keeps a
+/// read that outlives a timeout pending for the next request, so no bytes
+/// are lost to a cancelled read. Input left over from earlier requests is
+/// discarded before each new one.
+pub struct ModbusMaster<S: SerialPort + 'static, T: TimeSource> {
+    port: S,
+    response_timeout: Duration,
+    /// When the bus was last active, for the inter-frame delay
+    last_activity: Option<Timestamp>,
+    /// Read started by an earlier request and not yet finished
+    pending: Option<ReadFuture<S>>,
+    time: PhantomData<T>,
+}
+
+impl<S: SerialPort + 'static, T: TimeSource> fmt::Debug for ModbusMaster<S, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ModbusMaster")
+            .field("port", &self.port)
+            .field("response_timeout", &self.response_timeout)
+            .field("last_activity", &self.last_activity)
+            .field("reading", &self.pending.is_some())
+            .finish()
+    }
+}
+
+impl<S: SerialPort + 'static, T: TimeSource> ModbusMaster<S, T> {
+    pub const DEFAULT_RESPONSE_TIMEOUT: Duration = Duration::from_secs(1);
+
+    pub fn new(port: S) -> Self {
+        Self {
+            port,
+            response_timeout: Self::DEFAULT_RESPONSE_TIMEOUT,
+            last_activity: None,
+            pending: None,
+            time: PhantomData,
+        }
+    }
+
+    pub fn with_response_timeout(mut self, timeout: Duration) -> Self {
+        self.response_timeout = timeout;
+        self
+    }
+
+    pub fn port(&self) -> &S {
+        &self.port
+    }
+
+    pub fn port_mut(&mut self) -> &mut S {
+        &mut self.port
+    }
+
+    pub fn into_port(self) -> S {
+        self.port
+    }
+
+    /// Send a request to `unit` and wait for its response. Broadcast writes
+    /// return `Response::Written` without waiting.
+    pub async fn execute(&mut self, unit: u8, request: &Request) -> Result<Response> {
+        request.validate()?;
+        if unit == BROADCAST && !request.is_write() {
+            return Err(Error::ConfigError(
+                "Only writes can be broadcast".to_string(),
+            ));
+        }
+
+        self.wait_for_silence().await;
+        self.discard_stale_input().await;
+        let message = Message::new(T::now_millis(), Direction::Out, request.encode(unit));
+        self.port.write(message).await?;
+        self.last_activity = Some(T::now_millis());
+
+        if unit == BROADCAST {
+            return Ok(Response::Written);
+        }
+        let frame = self.read_response(unit, request).await;
+        self.last_activity = Some(T::now_millis());
+        request.decode_response(unit, &frame?)
+    }
+
+    pub async fn read_coils(&mut self, unit: u8, address: u16, count: u16) -> Result<Vec<bool>> {
+        self.read_bits(unit, Request::ReadCoils { address, count })
+            .await
+    }
+
+    pub async fn read_discrete_inputs(
+        &mut self,
+        unit: u8,
+        address: u16,
+        count: u16,
+    ) -> Result<Vec<bool>> {
+        self.read_bits(unit, Request::ReadDiscreteInputs { address, count })
+            .await
+    }
+
+    pub async fn read_holding_registers(
+        &mut self,
+        unit: u8,
+        address: u16,
+        count: u16,
+    ) -> Result<Vec<u16>> {
+        self.read_registers(unit, Request::ReadHoldingRegisters { address, count })
+            .await
+    }
+
+    pub async fn read_input_registers(
+        &mut self,
+        unit: u8,
+        address: u16,
+        count: u16,
+    ) -> Result<Vec<u16>> {
+        self.read_registers(unit, Request::ReadInputRegisters { address, count })
+            .await
+    }
+
+    pub async fn write_single_register(
+        &mut self,
+        unit: u8,
+        address: u16,
+        value: u16,
+    ) -> Result<()> {
+        self.execute(unit, &Request::WriteSingleRegister { address, value })
+            .await
+            .map(|_| ())
+    }
+
+    pub async fn write_multiple_registers(
+        &mut self,
+        unit: u8,
+        address: u16,
+        values: &[u16],
+    ) -> Result<()> {
+        let request = Request::WriteMultipleRegisters {
+            address,
+            values: values.to_vec(),
+        };
+        self.execute(unit, &request).await.map(|_| ())
+    }
+
+    async fn read_bits(&mut self, unit: u8, request: Request) -> Result<Vec<bool>> {
+        match self.execute(unit, &request).await? {
+            Response::Bits(bits) => Ok(bits),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    async fn read_registers(&mut self, unit: u8, request: Request) -> Result<Vec<u16>> {
+        match self.execute(unit, &request).await? {
+            Response::Registers(registers) => Ok(registers),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    async fn wait_for_silence(&self) {
+        let Some(last) = self.last_activity else {
+            return;
+        };
+        let delay = frame_delay(self.port.config());
+        let elapsed =
+            Duration::from_millis(T::now_millis().as_millis().saturating_sub(last.as_millis()));
+        if elapsed < delay {
+            T::sleep(delay - elapsed).await;
+        }
+    }
+
+    /// The pending read, or a new one on a clone of the port
+    fn take_read(&mut self) -> ReadFuture<S> {
+        self.pending
+            .take()
+            .unwrap_or_else(|| read_next(self.port.clone()))
+    }
+
+    /// Drop whatever has already arrived, e.g. a late reply to a request
+    /// that timed out, so it is not taken for the next response
+    async fn discard_stale_input(&mut self) {
+        let mut read = self.take_read();
+        loop {
+            tokio::select! {
+                biased;
+                (port, result) = &mut read => {
+                    read = read_next(port);
+                    // A failing port fails again at once; stop there
+                    if result.is_err() {
+                        break;
+                    }
+                }
+                () = std::future::ready(()) => break,
+            }
+        }
+        self.pending = Some(read);
+    }
+
+    /// Collect chunks until the response is complete or the timeout passes.
+    /// Each read is raced against the deadline, since some ports (e.g. Web
+    /// Serial) have no read timeout of their own; a read still running at
+    /// the deadline is kept for the next request.
+    async fn read_response(&mut self, unit: u8, request: &Request) -> Result<Vec<u8>> {
+        let mut read = self.take_read();
+        let result = self.collect_response(&mut read, unit, request).await;
+        self.pending = Some(read);
+        result
+    }
+
+    async fn collect_response(
+        &self,
+        read: &mut ReadFuture<S>,
+        unit: u8,
+        request: &Request,
+    ) -> Result<Vec<u8>> {
+        let deadline = T::now_millis().as_millis() + self.response_timeout.as_millis() as u64;
+        let timeout = Error::Timeout(format!(
+            "No complete Modbus response from unit {} within {:?}",
+            unit, self.response_timeout
+        ));
+        let mut frame = Vec::new();
+        loop {
+            let remaining = deadline.saturating_sub(T::now_millis().as_millis());
+            if remaining == 0 {
+                return Err(timeout);
+            }
+            let result = tokio::select! {
+                (port, result) = &mut *read => {
+                    *read = read_next(port);
+                    result
+                }
+                () = T::sleep(Duration::from_millis(remaining)) => return Err(timeout),
+            };
+            match result {
+                Ok(chunk) => frame.extend_from_slice(chunk.bytes()),
+                Err(Error::Timeout(_)) => {}
+                Err(e) => return Err(e),
+            }
+            if let Some(len) = request.response_len(&frame) {
+                if frame.len() >= len {
+                    // Anything past the frame is line noise
+                    frame.truncate(len);
+                    return Ok(frame);
+                }
+            }
+        }
+    }
+}
+
+fn unexpected(response: Response) -> Error {
+    Error::ParseError(format!("Unexpected Modbus response {:?}", response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_crc(body: &[u8]) -> Vec<u8> {
+        let mut frame = body.to_vec();
+        frame.extend(crc16_modbus(body).to_le_bytes());
+        frame
+    }
+
+    #[test]
+    fn test_encode_requests() {
+        // Reference frame: read 2 holding registers at 0 from unit 1
+        assert_eq!(
+            Request::ReadHoldingRegisters {
+                address: 0,
+                count: 2
+            }
+            .encode(1),
+            vec![0x01, 0x03, 0x00, 0x00, 0x00, 0x02, 0xC4, 0x0B]
+        );
+        let frame = Request::WriteMultipleRegisters {
+            address: 0x0010,
+            values: vec![0x000A, 0x0102],
+        }
+        .encode(0x11);
+        assert_eq!(
+            frame[..frame.len() - 2],
+            [0x11, 0x10, 0x00, 0x10, 0x00, 0x02, 0x04, 0x00, 0x0A, 0x01, 0x02]
+        );
+    }
+
+    #[test]
+    fn test_validate_quantities() {
+        assert!(Request::ReadCoils {
+            address: 0,
+            count: 0
+        }
+        .validate()
+        .is_err());
+        assert!(Request::ReadInputRegisters {
+            address: 0,
+            count: 126
+        }
+        .validate()
+        .is_err());
+        assert!(Request::ReadDiscreteInputs {
+            address: 0,
+            count: 2000
+        }
+        .validate()
+        .is_ok());
+    }
+
+    #[test]
+    fn test_decode_register_response() {
+        let request = Request::ReadInputRegisters {
+            address: 8,
+            count: 2,
+        };
+        let response = with_crc(&[0x01, 0x04, 0x04, 0x00, 0x0A, 0xFF, 0xFE]);
+        assert_eq!(request.response_len(&response[..2]), None);
+        assert_eq!(request.response_len(&response[..3]), Some(9));
+        assert_eq!(
+            request.decode_response(1, &response).unwrap(),
+            Response::Registers(vec![0x000A, 0xFFFE])
+        );
+        assert!(request.decode_response(2, &response).is_err());
+
+        let mut corrupted = response.clone();
+        corrupted[4] ^= 1;
+        assert!(request.decode_response(1, &corrupted).is_err());
+    }
+
+    #[test]
+    fn test_decode_bits_and_writes() {
+        let request = Request::ReadCoils {
+            address: 0,
+            count: 10,
+        };
+        let response = with_crc(&[0x01, 0x01, 0x02, 0b0000_0101, 0b0000_0010]);
+        let Response::Bits(bits) = request.decode_response(1, &response).unwrap() else {
+            panic!("expected bits");
+        };
+        assert_eq!(bits.len(), 10);
+        assert!(bits[0] && !bits[1] && bits[2] && bits[9]);
+
+        let request = Request::WriteSingleRegister {
+            address: 1,
+            value: 3,
+        };
+        let echo = request.encode(1);
+        assert_eq!(request.response_len(&echo), Some(8));
+        assert_eq!(
+            request.decode_response(1, &echo).unwrap(),
+            Response::Written
+        );
+    }
+
+    #[test]
+    fn test_decode_exception() {
+        let request = Request::ReadHoldingRegisters {
+            address: 0x100,
+            count: 1,
+        };
+        let response = with_crc(&[0x01, 0x83, 0x02]);
+        assert_eq!(request.response_len(&response), Some(5));
+        assert_eq!(
+            request.decode_response(1, &response),
+            Err(Error::ReadError(
+                "Modbus exception 0x02 (illegal data address) from unit 1".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_frame_delay() {
+        let config = PortConfig::new(9600, 8, 1);
+        // 10 bits per character at 9600 baud: 3.5 chars = 3.646 ms
+        assert_eq!(frame_delay(&config), Duration::from_micros(3645));
+        let config = config.with_parity(Parity::Even);
+        assert_eq!(frame_delay(&config), Duration::from_micros(4010));
+        assert_eq!(
+            frame_delay(&PortConfig::new(115200, 8, 1)),
+            Duration::from_micros(1750)
+        );
+    }
+
+    mod master {
+        use std::ops::ControlFlow;
+
+        use super::super::*;
+        use super::with_crc;
+        use crate::serial::mock::{MockSerialPort, MockTimeSource};
+        use crate::serial::SerialPortConfig;
+
+        type Master = ModbusMaster<MockSerialPort, MockTimeSource>;
+
+        /// Slave 7 answering register reads with `address + offset` and
+        /// acknowledging writes
+        fn slave() -> MockSerialPort {
+            MockSerialPort::responder(|request| {
+                if request[0] != 7 {
+                    return None;
+                }
+                let address = u16::from_be_bytes([request[2], request[3]]);
+                let count = u16::from_be_bytes([request[4], request[5]]);
+                match request[1] {
+                    0x03 | 0x04 => {
+                        let mut body = vec![7, request[1], (count * 2) as u8];
+                        for i in 0..count {
+                            body.extend((address + i).to_be_bytes());
+                        }
+                        Some(with_crc(&body))
+                    }
+                    0x06 | 0x10 => Some(with_crc(&request[..6])),
+                    _ => Some(with_crc(&[7, request[1] | 0x80, 0x01])),
+                }
+            })
+        }
+
+        async fn master(port: MockSerialPort) -> Master {
+            let mut master = Master::new(port);
+            master.port_mut().open().await.unwrap();
+            master
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn test_read_and_write() {
+            let port = slave();
+            let mut master = master(port.clone()).await;
+
+            assert_eq!(
+                master.read_holding_registers(7, 100, 3).await.unwrap(),
+                vec![100, 101, 102]
+            );
+            master.write_single_register(7, 5, 1).await.unwrap();
+            master
+                .write_multiple_registers(7, 5, &[1, 2])
+                .await
+                .unwrap();
+            assert_eq!(
+                master.read_coils(7, 0, 1).await,
+                Err(Error::ReadError(
+                    "Modbus exception 0x01 (illegal function) from unit 7".to_string()
+                ))
+            );
+
+            let writes = port.writes();
+            assert_eq!(writes.len(), 4);
+            assert_eq!(
+                writes[0].bytes(),
+                Request::ReadHoldingRegisters {
+                    address: 100,
+                    count: 3
+                }
+                .encode(7)
+            );
+            // Requests are separated by at least the inter-frame delay
+            let delay = frame_delay(master.port().config()).as_millis() as u64;
+            for pair in writes.windows(2) {
+                assert!(pair[1].timestamp().as_millis() - pair[0].timestamp().as_millis() >= delay);
+            }
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn test_timeout_and_broadcast() {
+            let port = slave();
+            let mut master = master(port.clone())
+                .await
+                .with_response_timeout(Duration::from_millis(300));
+
+            assert!(matches!(
+                master.read_input_registers(9, 0, 1).await,
+                Err(Error::Timeout(_))
+            ));
+            master.write_single_register(BROADCAST, 1, 2).await.unwrap();
+            assert!(master.read_input_registers(BROADCAST, 0, 1).await.is_err());
+            assert_eq!(port.writes().len(), 2);
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn test_timeout_without_read_timeout() {
+            // Reads on this port only give up after an hour
+            let config = PortConfig::default().with_read_timeout(Duration::from_secs(3600));
+            let port = slave().with_config(config);
+            let mut master = master(port)
+                .await
+                .with_response_timeout(Duration::from_millis(300));
+
+            let start = tokio::time::Instant::now();
+            assert!(matches!(
+                master.read_input_registers(9, 0, 1).await,
+                Err(Error::Timeout(_))
+            ));
+            assert!(start.elapsed() < Duration::from_secs(1));
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn test_late_reply_after_timeout() {
+            // Unit 7 misses the first request and answers the second
+            let mut requests = 0;
+            let port = MockSerialPort::responder(move |_| {
+                requests += 1;
+                (requests > 1).then(|| with_crc(&[7, 0x03, 2, 0, 2]))
+            })
+            .with_config(PortConfig::default().with_read_timeout(Duration::from_secs(3600)));
+            let mut master = master(port.clone())
+                .await
+                .with_response_timeout(Duration::from_millis(300));
+
+            assert!(matches!(
+                master.read_holding_registers(7, 0, 1).await,
+                Err(Error::Timeout(_))
+            ));
+            // The reply to the first request arrives after its timeout
+            port.push_input(&with_crc(&[7, 0x03, 2, 0, 1]));
+            tokio::time::sleep(Duration::from_millis(10)).await;
+
+            assert_eq!(
+                master.read_holding_registers(7, 0, 1).await.unwrap(),
+                vec![2]
+            );
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn test_poller() {
+            let mut master = master(slave()).await;
+            let mut poller = Poller::new(vec![
+                PollItem::new("fast", 7, RegisterTable::HoldingRegisters, 10)
+                    .with_interval(Duration::from_millis(100)),
+                PollItem::new("slow", 7, RegisterTable::InputRegisters, 20)
+                    .with_scale(0.5)
+                    .with_interval(Duration::from_millis(300)),
+            ])
+            .unwrap();
+
+            let mut points = Vec::new();
+            poller
+                .run(&mut master, |result| {
+                    points.push(result.unwrap());
+                    if points.len() == 6 {
+                        ControlFlow::Break(())
+                    } else {
+                        ControlFlow::Continue(())
+                    }
+                })
+                .await;
+
+            let labels: Vec<_> = points.iter().map(|p| p.label().unwrap()).collect();
+            assert_eq!(labels, vec!["fast", "slow", "fast", "fast", "fast", "slow"]);
+            assert_eq!(points[0].value(), 10.0);
+            assert_eq!(points[1].value(), 10.0);
+        }
+    }
+}
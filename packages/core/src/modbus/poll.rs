@@ -0,0 +1,365 @@
+use std::ops::ControlFlow;
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::{ModbusMaster, Request, Response};
+use crate::data::{Point, Timestamp};
+use crate::serial::SerialPort;
+use crate::system::TimeSource;
+use crate::{Error, Result};
+
+/// The four Modbus data tables
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RegisterTable {
+    Coils,
+    DiscreteInputs,
+    HoldingRegisters,
+    InputRegisters,
+}
+
+/// How the registers of a polled value are interpreted. 32-bit formats span
+/// two registers, high word first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueFormat {
+    #[default]
+    U16,
+    I16,
+    U32,
+    I32,
+    F32,
+}
+
+impl ValueFormat {
+    /// Number of registers the value spans
+    pub fn registers(self) -> u16 {
+        match self {
+            ValueFormat::U16 | ValueFormat::I16 => 1,
+            ValueFormat::U32 | ValueFormat::I32 | ValueFormat::F32 => 2,
+        }
+    }
+
+    fn decode(self, registers: &[u16]) -> f64 {
+        let wide = || ((registers[0] as u32) << 16) | registers[1] as u32;
+        match self {
+            ValueFormat::U16 => registers[0] as f64,
+            ValueFormat::I16 => registers[0] as i16 as f64,
+            ValueFormat::U32 => wide() as f64,
+            ValueFormat::I32 => wide() as i32 as f64,
+            ValueFormat::F32 => f32::from_bits(wide()) as f64,
+        }
+    }
+}
+
+/// One value polled periodically and plotted as a point labelled `label`
+/// with value `raw * scale`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PollItem {
+    pub label: String,
+    pub unit: u8,
+    pub table: RegisterTable,
+    pub address: u16,
+    /// Ignored for coils and discrete inputs, which read as 0 or 1
+    #[serde(default)]
+    pub format: ValueFormat,
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+    #[serde(default = "default_interval")]
+    pub interval: Duration,
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+fn default_interval() -> Duration {
+    Duration::from_secs(1)
+}
+
+impl PollItem {
+    pub fn new(label: impl Into<String>, unit: u8, table: RegisterTable, address: u16) -> Self {
+        Self {
+            label: label.into(),
+            unit,
+            table,
+            address,
+            format: ValueFormat::default(),
+            scale: default_scale(),
+            interval: default_interval(),
+        }
+    }
+
+    pub fn with_format(mut self, format: ValueFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.label.is_empty() {
+            return Err(Error::ConfigError("Poll item has no label".to_string()));
+        }
+        if self.unit == super::BROADCAST {
+            return Err(Error::ConfigError(format!(
+                "Poll item '{}' cannot read from the broadcast unit",
+                self.label
+            )));
+        }
+        if self.interval.is_zero() {
+            return Err(Error::ConfigError(format!(
+                "Poll item '{}' has a zero interval",
+                self.label
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn request(&self) -> Request {
+        let address = self.address;
+        let count = self.format.registers();
+        match self.table {
+            RegisterTable::Coils => Request::ReadCoils { address, count: 1 },
+            RegisterTable::DiscreteInputs => Request::ReadDiscreteInputs { address, count: 1 },
+            RegisterTable::HoldingRegisters => Request::ReadHoldingRegisters { address, count },
+            RegisterTable::InputRegisters => Request::ReadInputRegisters { address, count },
+        }
+    }
+
+    /// Turn the response to `request()` into a point
+    pub fn to_point(&self, timestamp: Timestamp, response: &Response) -> Result<Point> {
+        let raw = match response {
+            Response::Bits(bits) if !bits.is_empty() => bits[0] as u8 as f64,
+            Response::Registers(registers)
+                if registers.len() >= self.format.registers() as usize =>
+            {
+                self.format.decode(registers)
+            }
+            other => {
+                return Err(Error::ParseError(format!(
+                    "Unexpected Modbus response for '{}': {:?}",
+                    self.label, other
+                )))
+            }
+        };
+        Ok(Point::new(timestamp, raw * self.scale).with_label(&self.label))
+    }
+}
+
+/// Parses `label unit table address [format] [scale] [interval_ms]`, e.g.
+/// `temp 1 holding 100 i16 0.1 500`. Tables are `coil`, `discrete`,
+/// `holding` and `input`.
+impl FromStr for PollItem {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |what: &str, value: &str| {
+            Error::ConfigError(format!("Invalid {} '{}' in poll item '{}'", what, value, s))
+        };
+        let words: Vec<&str> = s.split_whitespace().collect();
+        if !(4..=7).contains(&words.len()) {
+            return Err(Error::ConfigError(format!(
+                "Poll item '{}' needs: label unit table address [format] [scale] [interval_ms]",
+                s
+            )));
+        }
+
+        let table = match words[2] {
+            "coil" | "coils" => RegisterTable::Coils,
+            "discrete" => RegisterTable::DiscreteInputs,
+            "holding" => RegisterTable::HoldingRegisters,
+            "input" => RegisterTable::InputRegisters,
+            other => return Err(invalid("table", other)),
+        };
+        let mut item = PollItem::new(
+            words[0],
+            words[1].parse().map_err(|_| invalid("unit", words[1]))?,
+            table,
+            words[3].parse().map_err(|_| invalid("address", words[3]))?,
+        );
+        if let Some(&format) = words.get(4) {
+            item.format = match format {
+                "u16" => ValueFormat::U16,
+                "i16" => ValueFormat::I16,
+                "u32" => ValueFormat::U32,
+                "i32" => ValueFormat::I32,
+                "f32" => ValueFormat::F32,
+                other => return Err(invalid("format", other)),
+            };
+        }
+        if let Some(&scale) = words.get(5) {
+            item.scale = scale.parse().map_err(|_| invalid("scale", scale))?;
+        }
+        if let Some(&interval) = words.get(6) {
+            let millis = interval
+                .parse()
+                .map_err(|_| invalid("interval", interval))?;
+            item.interval = Duration::from_millis(millis);
+        }
+        item.validate()?;
+        Ok(item)
+    }
+}
+
+/// Polls each item at its own interval through a `ModbusMaster`
+#[derive(Debug, Clone)]
+pub struct Poller {
+    items: Vec<PollItem>,
+    /// When each item is next due; `None` until first polled
+    due: Vec<Option<Timestamp>>,
+}
+
+impl Poller {
+    /// Fails if any item is invalid
+    pub fn new(items: Vec<PollItem>) -> Result<Self> {
+        for item in &items {
+            item.validate()?;
+        }
+        Ok(Self {
+            due: vec![None; items.len()],
+            items,
+        })
+    }
+
+    pub fn items(&self) -> &[PollItem] {
+        &self.items
+    }
+
+    /// Earliest time any item is due, or `None` if one is due right away
+    pub fn next_due(&self) -> Option<Timestamp> {
+        self.due.iter().copied().min().flatten()
+    }
+
+    /// Poll every item due at `now`, in order. Each result is a point or
+    /// the error that item's request ran into.
+    pub async fn poll_due<S: SerialPort + 'static, T: TimeSource>(
+        &mut self,
+        master: &mut ModbusMaster<S, T>,
+        now: Timestamp,
+    ) -> Vec<Result<Point>> {
+        let mut results = Vec::new();
+        for (item, due) in self.items.iter().zip(self.due.iter_mut()) {
+            if due.is_some_and(|due| due > now) {
+                continue;
+            }
+            *due = Some(Timestamp(
+                now.as_millis() + item.interval.as_millis() as u64,
+            ));
+            let result = master.execute(item.unit, &item.request()).await;
+            results.push(result.and_then(|response| item.to_point(T::now_millis(), &response)));
+        }
+        results
+    }
+
+    /// Poll forever, sleeping until items are due, until `on_result`
+    /// breaks
+    pub async fn run<S: SerialPort + 'static, T: TimeSource>(
+        &mut self,
+        master: &mut ModbusMaster<S, T>,
+        mut on_result: impl FnMut(Result<Point>) -> ControlFlow<()>,
+    ) {
+        if self.items.is_empty() {
+            return;
+        }
+        loop {
+            let now = T::now_millis();
+            if let Some(due) = self.next_due() {
+                if due > now {
+                    T::sleep(Duration::from_millis(due.as_millis() - now.as_millis())).await;
+                    continue;
+                }
+            }
+            for result in self.poll_due(master, now).await {
+                if on_result(result).is_break() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_value_formats() {
+        assert_eq!(ValueFormat::I16.decode(&[0xFFFE]), -2.0);
+        assert_eq!(ValueFormat::U32.decode(&[0x0001, 0x0002]), 65538.0);
+        assert_eq!(ValueFormat::I32.decode(&[0xFFFF, 0xFFFF]), -1.0);
+        let bits = 1.5f32.to_bits();
+        assert_eq!(
+            ValueFormat::F32.decode(&[(bits >> 16) as u16, bits as u16]),
+            1.5
+        );
+    }
+
+    #[test]
+    fn test_item_request_and_point() {
+        let item = PollItem::new("power", 3, RegisterTable::InputRegisters, 10)
+            .with_format(ValueFormat::U32)
+            .with_scale(0.5);
+        assert_eq!(
+            item.request(),
+            Request::ReadInputRegisters {
+                address: 10,
+                count: 2
+            }
+        );
+        let point = item
+            .to_point(Timestamp(1), &Response::Registers(vec![0, 9]))
+            .unwrap();
+        assert_eq!(point.label(), Some("power"));
+        assert_eq!(point.value(), 4.5);
+
+        let coil = PollItem::new("pump", 1, RegisterTable::Coils, 0);
+        let point = coil
+            .to_point(Timestamp(1), &Response::Bits(vec![true]))
+            .unwrap();
+        assert_eq!(point.value(), 1.0);
+        assert!(coil.to_point(Timestamp(1), &Response::Written).is_err());
+    }
+
+    #[test]
+    fn test_parse_item() {
+        let item: PollItem = "temp 1 holding 100 i16 0.1 500".parse().unwrap();
+        assert_eq!(
+            item,
+            PollItem::new("temp", 1, RegisterTable::HoldingRegisters, 100)
+                .with_format(ValueFormat::I16)
+                .with_scale(0.1)
+                .with_interval(Duration::from_millis(500))
+        );
+        let item: PollItem = "alarm 2 discrete 7".parse().unwrap();
+        assert_eq!(item.table, RegisterTable::DiscreteInputs);
+        assert_eq!(item.interval, Duration::from_secs(1));
+
+        assert!("temp 1 holding".parse::<PollItem>().is_err());
+        assert!("temp 1 table 0".parse::<PollItem>().is_err());
+        assert!("temp 0 holding 0".parse::<PollItem>().is_err());
+        assert!("temp 1 holding 0 u16 1 0".parse::<PollItem>().is_err());
+    }
+
+    #[test]
+    fn test_item_serialization() {
+        let item: PollItem = serde_json::from_str(
+            r#"{"label":"t","unit":1,"table":"holding_registers","address":5}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            item,
+            PollItem::new("t", 1, RegisterTable::HoldingRegisters, 5)
+        );
+    }
+}
@@ -1,17 +1,121 @@
+pub mod binary;
+pub mod config;
+pub mod delimited;
 pub mod expression;
+pub mod json;
+pub mod nmea;
+pub mod pipeline;
 pub mod regex;
 
-use crate::data::{Message, Point};
-use crate::Result;
+use ::regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::data::{Message, Point, Timestamp};
+use crate::{Error, Result};
 
 use expression::Expr;
-use regex::extract;
+use regex::{compile, extract_all_with, extract_with, ExtractionResult};
+
+pub use binary::{BinaryField, BinaryParser, BinarySchema, Endianness, FieldType};
+pub use config::ParserConfig;
+pub use delimited::DelimitedParser;
+pub use json::JsonParser;
+pub use nmea::NmeaParser;
+pub use pipeline::Pipeline;
+
+/// Common interface of the message parsers in this module
+pub trait Parse {
+    /// Turn one message into zero or more points
+    fn parse_message(&self, message: &Message) -> Result<Vec<Point>>;
+
+    /// Short human-readable summary of the configuration
+    fn description(&self) -> String;
+
+    /// Serializable configuration that rebuilds an equivalent parser
+    fn config(&self) -> ParserConfig;
+}
+
+/// Which regex matches in a message produce points
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchMode {
+    /// Only the first match
+    #[default]
+    First,
+    /// Every non-overlapping match, e.g. `t=21.3 h=40.1 p=1013.2`
+    All,
+}
+
+/// A (label expr, value expr) pair, kept both as source and compiled
+#[derive(Debug, Clone)]
+struct Field {
+    label_str: String,
+    value_str: String,
+    /// `None` when the label expression is empty
+    label: Option<Expr>,
+    value: Expr,
+}
+
+impl Field {
+    fn compile(label_str: String, value_str: String) -> Result<Self> {
+        Ok(Self {
+            label: compile_label(&label_str),
+            value: compile_value(&value_str)?,
+            label_str,
+            value_str,
+        })
+    }
+
+    /// Check the expressions against the pattern they will be evaluated on
+    fn check_captures(&self, regex: &Regex) -> Result<()> {
+        if let Some(label) = &self.label {
+            label.check_captures(regex)?;
+        }
+        self.value.check_captures(regex)
+    }
+
+    fn build_point(&self, timestamp: Timestamp, extraction: &ExtractionResult) -> Result<Point> {
+        let value: f64 = self.value.eval(extraction)?.try_into()?;
+        let mut point = Point::new(timestamp, value);
+
+        // If label_expr is provided, evaluate it
+        if let Some(label_expr) = &self.label {
+            let label: String = label_expr.eval(extraction)?.try_into()?;
+            point = point.with_label(label);
+        }
+
+        Ok(point)
+    }
+}
+
+/// Label expressions that are not valid syntax are used as literal text
+fn compile_label(label_expr: &str) -> Option<Expr> {
+    if label_expr.trim().is_empty() {
+        None
+    } else {
+        Some(Expr::new(label_expr))
+    }
+}
+
+/// Value expressions must be valid syntax
+fn compile_value(value_expr: &str) -> Result<Expr> {
+    Expr::parse(value_expr).map_err(|e| {
+        Error::ConfigError(format!("Invalid value expression '{}': {}", value_expr, e))
+    })
+}
 
-/// Regex-based parser using extraction and expression evaluation
+/// Regex-based parser using extraction and expression evaluation.
+///
+/// The pattern and expressions are compiled when set, and capture references
+/// checked against the pattern, so configuration errors surface from the
+/// constructor and setters rather than per message.
+#[derive(Debug, Clone)]
 pub struct Parser {
     pattern_str: String,
-    label_expr_str: String,
-    value_expr_str: String,
+    regex: Regex,
+    /// Primary field followed by any extra fields; never empty
+    fields: Vec<Field>,
+    match_mode: MatchMode,
 }
 
 impl Parser {
@@ -19,32 +123,67 @@ impl Parser {
         pattern: impl Into<String>,
         name_expr: impl Into<String>,
         value_expr: impl Into<String>,
-    ) -> Self {
-        Self {
-            pattern_str: pattern.into(),
-            label_expr_str: name_expr.into(),
-            value_expr_str: value_expr.into(),
-        }
+    ) -> Result<Self> {
+        let pattern_str = pattern.into();
+        let regex = compile(&pattern_str)?;
+        let field = Field::compile(name_expr.into(), value_expr.into())?;
+        field.check_captures(&regex)?;
+        Ok(Self {
+            regex,
+            pattern_str,
+            fields: vec![field],
+            match_mode: MatchMode::First,
+        })
+    }
+
+    pub fn with_match_mode(mut self, match_mode: MatchMode) -> Self {
+        self.match_mode = match_mode;
+        self
+    }
+
+    /// Also emit a point for this (label expr, value expr) pair from every
+    /// match, so `t=(\S+) h=(\S+)` can yield both `t` and `h`.
+    pub fn with_field(
+        mut self,
+        label_expr: impl Into<String>,
+        value_expr: impl Into<String>,
+    ) -> Result<Self> {
+        let field = Field::compile(label_expr.into(), value_expr.into())?;
+        field.check_captures(&self.regex)?;
+        self.fields.push(field);
+        Ok(self)
     }
 
+    /// Parse the first match into a single point using the primary
+    /// label/value expressions
     pub fn parse(&self, message: &Message) -> Result<Point> {
-        let extraction = extract(&self.pattern_str, message.text())?;
-        let value_expr = Expr::new(&self.value_expr_str).eval(&extraction)?;
-        let value: f64 = value_expr.try_into()?;
-        let mut point = Point::new(message.timestamp(), value);
+        let extraction = extract_with(&self.regex, message.text())?;
+        self.fields[0].build_point(message.timestamp(), &extraction)
+    }
 
-        // If label_expr is provided, evaluate it
-        if !self.label_expr_str.is_empty() {
-            let label_expr = Expr::new(&self.label_expr_str).eval(&extraction)?;
-            let label: String = label_expr.try_into()?;
-            point = point.with_label(label);
-        }
+    /// Parse every point the message yields according to the match mode
+    /// and fields. Fails if any field of any match fails to evaluate.
+    pub fn parse_all(&self, message: &Message) -> Result<Vec<Point>> {
+        let extractions = match self.match_mode {
+            MatchMode::First => vec![extract_with(&self.regex, message.text())?],
+            MatchMode::All => extract_all_with(&self.regex, message.text())?,
+        };
 
-        Ok(point)
+        let mut points = Vec::with_capacity(extractions.len() * self.fields.len());
+        for extraction in &extractions {
+            for field in &self.fields {
+                points.push(field.build_point(message.timestamp(), extraction)?);
+            }
+        }
+        Ok(points)
     }
 
+    /// Whether `line` holds a whole record for this parser, i.e. the
+    /// pattern matches it. A fragment of a line split across reads usually
+    /// does not; frame the input with `framing::LineFramer` first.
     pub fn is_complete(&self, line: &str) -> bool {
-        !line.trim().is_empty()
+        let line = line.trim_end_matches(['\r', '\n']);
+        !line.trim().is_empty() && self.regex.is_match(line)
     }
 
     pub fn pattern(&self) -> &str {
@@ -52,23 +191,114 @@ impl Parser {
     }
 
     pub fn label_expr(&self) -> &str {
-        &self.label_expr_str
+        &self.fields[0].label_str
     }
 
     pub fn value_expr(&self) -> &str {
-        &self.value_expr_str
+        &self.fields[0].value_str
+    }
+
+    /// Replace the pattern; the parser is unchanged if it does not compile
+    /// or lacks a capture group the fields refer to
+    pub fn set_pattern(&mut self, pattern: impl Into<String>) -> Result<()> {
+        let pattern_str = pattern.into();
+        let regex = compile(&pattern_str)?;
+        for field in &self.fields {
+            field.check_captures(&regex)?;
+        }
+        self.regex = regex;
+        self.pattern_str = pattern_str;
+        Ok(())
+    }
+
+    /// Replace the label expression. Invalid syntax is used as literal
+    /// text, but a reference to a missing capture group is an error.
+    pub fn set_label_expr(&mut self, label_expr: impl Into<String>) -> Result<()> {
+        let label_str = label_expr.into();
+        let label = compile_label(&label_str);
+        if let Some(label) = &label {
+            label.check_captures(&self.regex)?;
+        }
+        let field = &mut self.fields[0];
+        field.label = label;
+        field.label_str = label_str;
+        Ok(())
+    }
+
+    /// Replace the value expression; the parser is unchanged if it is invalid
+    pub fn set_value_expr(&mut self, value_expr: impl Into<String>) -> Result<()> {
+        let value_str = value_expr.into();
+        let value = compile_value(&value_str)?;
+        value.check_captures(&self.regex)?;
+        let field = &mut self.fields[0];
+        field.value = value;
+        field.value_str = value_str;
+        Ok(())
     }
 
-    pub fn set_pattern(&mut self, pattern: impl Into<String>) {
-        self.pattern_str = pattern.into();
+    pub fn match_mode(&self) -> MatchMode {
+        self.match_mode
     }
 
-    pub fn set_label_expr(&mut self, label_expr: impl Into<String>) {
-        self.label_expr_str = label_expr.into();
+    pub fn set_match_mode(&mut self, match_mode: MatchMode) {
+        self.match_mode = match_mode;
     }
 
-    pub fn set_value_expr(&mut self, value_expr: impl Into<String>) {
-        self.value_expr_str = value_expr.into();
+    /// (label expr, value expr) pairs beyond the primary one
+    pub fn extra_fields(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.fields[1..]
+            .iter()
+            .map(|field| (field.label_str.as_str(), field.value_str.as_str()))
+    }
+
+    /// Replace the extra fields; the parser is unchanged if any is invalid
+    pub fn set_extra_fields(&mut self, fields: Vec<(String, String)>) -> Result<()> {
+        let compiled = fields
+            .into_iter()
+            .map(|(label, value)| {
+                let field = Field::compile(label, value)?;
+                field.check_captures(&self.regex)?;
+                Ok(field)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        self.fields.truncate(1);
+        self.fields.extend(compiled);
+        Ok(())
+    }
+}
+
+impl Parse for Parser {
+    fn parse_message(&self, message: &Message) -> Result<Vec<Point>> {
+        self.parse_all(message)
+    }
+
+    fn description(&self) -> String {
+        let mut description = format!(
+            "Regex /{}/ {} = {}",
+            self.pattern(),
+            self.label_expr(),
+            self.value_expr()
+        );
+        for (label, value) in self.extra_fields() {
+            description.push_str(&format!(", {} = {}", label, value));
+        }
+        if self.match_mode == MatchMode::All {
+            description.push_str(" (all matches)");
+        }
+        description
+    }
+
+    fn config(&self) -> ParserConfig {
+        ParserConfig::Regex {
+            pattern: self.pattern_str.clone(),
+            label: self.label_expr().to_string(),
+            value: self.value_expr().to_string(),
+            match_mode: self.match_mode,
+            fields: self
+                .extra_fields()
+                .map(|(label, value)| (label.to_string(), value.to_string()))
+                .collect(),
+        }
     }
 }
 
@@ -80,7 +310,7 @@ mod tests {
 
     #[test]
     fn test_regex_parser_simple_numeric() {
-        let parser = Parser::new(r"(\d+\.\d+)", "", "$1");
+        let parser = Parser::new(r"(\d+\.\d+)", "", "$1").unwrap();
         let point = parser
             .parse(&Message::new(
                 Timestamp::from_millis(0),
@@ -94,7 +324,7 @@ mod tests {
 
     #[test]
     fn test_regex_parser_with_name() {
-        let parser = Parser::new(r"(\w+): (\d+\.\d+)", "$1", "$2");
+        let parser = Parser::new(r"(\w+): (\d+\.\d+)", "$1", "$2").unwrap();
         let point = parser
             .parse(&Message::new(
                 Timestamp::from_millis(0),
@@ -108,7 +338,7 @@ mod tests {
 
     #[test]
     fn test_regex_parser_multiple_captures() {
-        let parser = Parser::new(r"(\w+)=(\d+)", "$1", "$2");
+        let parser = Parser::new(r"(\w+)=(\d+)", "$1", "$2").unwrap();
         let point = parser
             .parse(&Message::new(
                 Timestamp::from_millis(0),
@@ -120,9 +350,152 @@ mod tests {
         assert_eq!(point.label(), Some("Humidity"));
     }
 
+    #[test]
+    fn test_regex_parser_named_captures() {
+        let parser =
+            Parser::new(r"(?P<sensor>\w+)=(?P<val>[-\d.]+)", "${sensor}", "${val}").unwrap();
+        let point = parser
+            .parse(&Message::new(
+                Timestamp::from_millis(0),
+                Direction::In,
+                "pressure=-1.25",
+            ))
+            .unwrap();
+        assert_eq!(point.value(), -1.25);
+        assert_eq!(point.label(), Some("pressure"));
+    }
+
+    #[test]
+    fn test_regex_parser_all_matches() {
+        let parser = Parser::new(r"(\w+)=([-\d.]+)", "$1", "$2")
+            .unwrap()
+            .with_match_mode(MatchMode::All);
+        let points = parser
+            .parse_all(&Message::new(
+                Timestamp::from_millis(7),
+                Direction::In,
+                "t=21.3 h=40.1 p=1013.2",
+            ))
+            .unwrap();
+        let labels: Vec<_> = points.iter().map(|p| p.label().unwrap()).collect();
+        let values: Vec<_> = points.iter().map(|p| p.value()).collect();
+        assert_eq!(labels, vec!["t", "h", "p"]);
+        assert_eq!(values, vec![21.3, 40.1, 1013.2]);
+        assert!(points.iter().all(|p| p.timestamp() == Timestamp(7)));
+    }
+
+    #[test]
+    fn test_regex_parser_multiple_fields() {
+        let parser = Parser::new(r"t=(\S+) h=(\S+) p=(\S+)", "temp", "$1")
+            .unwrap()
+            .with_field("hum", "$2")
+            .unwrap()
+            .with_field("press", "$3 / 10")
+            .unwrap();
+        let points = parser
+            .parse_all(&Message::new(
+                Timestamp::from_millis(0),
+                Direction::In,
+                "t=21.3 h=40.1 p=1013.2",
+            ))
+            .unwrap();
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[1].label(), Some("hum"));
+        assert_eq!(points[1].value(), 40.1);
+        assert_eq!(points[2].label(), Some("press"));
+        assert!((points[2].value() - 101.32).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_regex_parser_first_match_only() {
+        let parser = Parser::new(r"(\w+)=(\d+)", "$1", "$2").unwrap();
+        let points = parser
+            .parse_all(&Message::new(
+                Timestamp::from_millis(0),
+                Direction::In,
+                "a=1 b=2",
+            ))
+            .unwrap();
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].label(), Some("a"));
+    }
+
+    #[test]
+    fn test_regex_parser_config_errors() {
+        assert!(matches!(
+            Parser::new(r"(unclosed", "", "$1"),
+            Err(Error::ConfigError(_))
+        ));
+        assert!(matches!(
+            Parser::new(r"(\d+)", "", "$1 *"),
+            Err(Error::ConfigError(_))
+        ));
+        assert!(Parser::new(r"(\d+)", "", "$1")
+            .unwrap()
+            .with_field("x", "sqrt(")
+            .is_err());
+    }
+
+    #[test]
+    fn test_regex_parser_missing_captures() {
+        assert!(matches!(
+            Parser::new(r"(\d+)", "", "$2"),
+            Err(Error::ConfigError(_))
+        ));
+        assert!(matches!(
+            Parser::new(r"(?<t>\d+)", "${name}", "${t}"),
+            Err(Error::ConfigError(_))
+        ));
+
+        let mut parser = Parser::new(r"(?<t>\d+) (\d+)", "${t}", "$2").unwrap();
+        assert!(parser.clone().with_field("h", "$3").is_err());
+        assert!(parser
+            .set_extra_fields(vec![("h".to_string(), "${h}".to_string())])
+            .is_err());
+        assert!(parser.set_value_expr("$3").is_err());
+        assert!(parser.set_label_expr("$3").is_err());
+
+        // The pattern cannot drop a group the fields still use
+        assert!(parser.set_pattern(r"(\d+) (\d+)").is_err());
+        assert!(parser.set_pattern(r"(?<t>\d+)").is_err());
+        assert_eq!(parser.pattern(), r"(?<t>\d+) (\d+)");
+        parser.set_pattern(r"(?<t>\d+),(\d+),(\d+)").unwrap();
+        parser.set_value_expr("$3").unwrap();
+    }
+
+    #[test]
+    fn test_regex_parser_setters_recompile() {
+        let mut parser = Parser::new(r"a=(\d+)", "a", "$1").unwrap();
+        let message = Message::new(Timestamp::from_millis(0), Direction::In, "b=4");
+        assert!(parser.parse(&message).is_err());
+
+        parser.set_pattern(r"b=(\d+)").unwrap();
+        parser.set_label_expr("b").unwrap();
+        parser.set_value_expr("$1 * 2").unwrap();
+        let point = parser.parse(&message).unwrap();
+        assert_eq!(point.label(), Some("b"));
+        assert_eq!(point.value(), 8.0);
+
+        // Invalid updates leave the parser untouched
+        assert!(parser.set_pattern("(").is_err());
+        assert!(parser.set_value_expr("$1 +").is_err());
+        assert_eq!(parser.pattern(), r"b=(\d+)");
+        assert_eq!(parser.value_expr(), "$1 * 2");
+        assert_eq!(parser.parse(&message).unwrap().value(), 8.0);
+    }
+
+    #[test]
+    fn test_regex_parser_is_complete() {
+        let parser = Parser::new(r"^Temp: (\d+\.\d+)$", "", "$1").unwrap();
+        assert!(parser.is_complete("Temp: 25.5\r\n"));
+        assert!(!parser.is_complete("Temp: 2"));
+        assert!(!parser.is_complete("5.5"));
+        assert!(!parser.is_complete("  "));
+    }
+
     #[test]
     fn test_regex_parser_no_match() {
-        let parser = Parser::new(r"Temperature: (\d+\.\d+)", "", "$1");
+        let parser = Parser::new(r"Temperature: (\d+\.\d+)", "", "$1").unwrap();
         let result = parser.parse(&Message::new(
             Timestamp::from_millis(0),
             Direction::In,
@@ -133,7 +506,7 @@ mod tests {
 
     #[test]
     fn test_regex_parser_invalid_number() {
-        let parser = Parser::new(r"(\w+)", "", "$1");
+        let parser = Parser::new(r"(\w+)", "", "$1").unwrap();
         let result = parser.parse(&Message::new(
             Timestamp::from_millis(0),
             Direction::In,
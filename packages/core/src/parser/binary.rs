@@ -0,0 +1,311 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Parse, ParserConfig};
+use crate::data::{Message, Point};
+use crate::{Error, Result};
+
+/// Primitive type of a field in a binary frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldType {
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    F32,
+    F64,
+}
+
+impl FieldType {
+    /// Size in bytes
+    pub fn size(self) -> usize {
+        match self {
+            FieldType::U8 | FieldType::I8 => 1,
+            FieldType::U16 | FieldType::I16 => 2,
+            FieldType::U32 | FieldType::I32 | FieldType::F32 => 4,
+            FieldType::F64 => 8,
+        }
+    }
+
+    fn decode(self, bytes: &[u8], endianness: Endianness) -> f64 {
+        macro_rules! read {
+            ($t:ty) => {{
+                let raw = bytes.try_into().expect("slice has the field size");
+                match endianness {
+                    Endianness::Little => <$t>::from_le_bytes(raw),
+                    Endianness::Big => <$t>::from_be_bytes(raw),
+                }
+            }};
+        }
+        match self {
+            FieldType::U8 => bytes[0] as f64,
+            FieldType::I8 => bytes[0] as i8 as f64,
+            FieldType::U16 => read!(u16) as f64,
+            FieldType::I16 => read!(i16) as f64,
+            FieldType::U32 => read!(u32) as f64,
+            FieldType::I32 => read!(i32) as f64,
+            FieldType::F32 => read!(f32) as f64,
+            FieldType::F64 => read!(f64),
+        }
+    }
+}
+
+/// Byte order of a multi-byte field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Endianness {
+    #[default]
+    Little,
+    Big,
+}
+
+/// One field of a binary frame, emitted as a point labelled `name` with
+/// value `raw * scale`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BinaryField {
+    pub name: String,
+    /// Byte offset from the start of the frame
+    pub offset: usize,
+    #[serde(rename = "type")]
+    pub field_type: FieldType,
+    #[serde(default)]
+    pub endianness: Endianness,
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+impl BinaryField {
+    pub fn new(name: impl Into<String>, offset: usize, field_type: FieldType) -> Self {
+        Self {
+            name: name.into(),
+            offset,
+            field_type,
+            endianness: Endianness::default(),
+            scale: default_scale(),
+        }
+    }
+
+    pub fn with_endianness(mut self, endianness: Endianness) -> Self {
+        self.endianness = endianness;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Offset one past the last byte of the field
+    pub fn end(&self) -> usize {
+        self.offset + self.field_type.size()
+    }
+}
+
+/// Layout of a fixed-format binary frame
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BinarySchema {
+    pub fields: Vec<BinaryField>,
+    /// Exact frame length; frames of any other length are rejected. When
+    /// unset, frames only need to be long enough for every field.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub length: Option<usize>,
+}
+
+impl BinarySchema {
+    pub fn new(fields: Vec<BinaryField>) -> Self {
+        Self {
+            fields,
+            length: None,
+        }
+    }
+
+    pub fn with_length(mut self, length: usize) -> Self {
+        self.length = Some(length);
+        self
+    }
+
+    /// Bytes needed to decode every field
+    pub fn min_length(&self) -> usize {
+        self.fields.iter().map(BinaryField::end).max().unwrap_or(0)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        for field in &self.fields {
+            if field.name.is_empty() {
+                return Err(Error::ConfigError(format!(
+                    "Field at offset {} has no name",
+                    field.offset
+                )));
+            }
+            if !field.scale.is_finite() {
+                return Err(Error::ConfigError(format!(
+                    "Field '{}' has a non-finite scale",
+                    field.name
+                )));
+            }
+        }
+        if let Some(length) = self.length {
+            if self.min_length() > length {
+                return Err(Error::ConfigError(format!(
+                    "Fields need {} bytes but frames are {} bytes",
+                    self.min_length(),
+                    length
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Decodes fixed-layout binary frames (one frame per message) into labelled
+/// points sharing the message timestamp
+#[derive(Debug, Clone, PartialEq)]
+pub struct BinaryParser {
+    schema: BinarySchema,
+}
+
+impl BinaryParser {
+    /// Fails if the schema is invalid
+    pub fn new(schema: BinarySchema) -> Result<Self> {
+        schema.validate()?;
+        Ok(Self { schema })
+    }
+
+    pub fn schema(&self) -> &BinarySchema {
+        &self.schema
+    }
+}
+
+impl Parse for BinaryParser {
+    fn parse_message(&self, message: &Message) -> Result<Vec<Point>> {
+        let frame = message.bytes();
+        match self.schema.length {
+            Some(length) if frame.len() != length => {
+                return Err(Error::ParseError(format!(
+                    "Expected a {}-byte frame, got {} bytes",
+                    length,
+                    frame.len()
+                )));
+            }
+            _ if frame.len() < self.schema.min_length() => {
+                return Err(Error::ParseError(format!(
+                    "Frame of {} bytes is shorter than the {} bytes the schema needs",
+                    frame.len(),
+                    self.schema.min_length()
+                )));
+            }
+            _ => {}
+        }
+
+        Ok(self
+            .schema
+            .fields
+            .iter()
+            .map(|field| {
+                let raw = field
+                    .field_type
+                    .decode(&frame[field.offset..field.end()], field.endianness);
+                Point::new(message.timestamp(), raw * field.scale).with_label(&field.name)
+            })
+            .collect())
+    }
+
+    fn description(&self) -> String {
+        let names: Vec<_> = self.schema.fields.iter().map(|f| f.name.as_str()).collect();
+        format!("Binary {}", names.join(", "))
+    }
+
+    fn config(&self) -> ParserConfig {
+        ParserConfig::Binary(self.schema.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Direction, Timestamp};
+
+    fn parse(parser: &BinaryParser, bytes: &[u8]) -> Result<Vec<(String, f64)>> {
+        let message = Message::new(Timestamp(3), Direction::In, bytes);
+        Ok(parser
+            .parse_message(&message)?
+            .into_iter()
+            .map(|p| (p.label().unwrap().to_string(), p.value()))
+            .collect())
+    }
+
+    #[test]
+    fn test_decode_fields() {
+        let schema = BinarySchema::new(vec![
+            BinaryField::new("id", 0, FieldType::U8),
+            BinaryField::new("temp", 1, FieldType::I16).with_scale(0.01),
+            BinaryField::new("count", 3, FieldType::U32).with_endianness(Endianness::Big),
+            BinaryField::new("volts", 7, FieldType::F32),
+            BinaryField::new("exact", 11, FieldType::F64),
+        ]);
+        let parser = BinaryParser::new(schema).unwrap();
+
+        let mut frame = vec![7];
+        frame.extend((-2150i16).to_le_bytes());
+        frame.extend(70000u32.to_be_bytes());
+        frame.extend(3.5f32.to_le_bytes());
+        frame.extend(0.125f64.to_le_bytes());
+
+        let points = parse(&parser, &frame).unwrap();
+        assert_eq!(points[0], ("id".to_string(), 7.0));
+        assert_eq!(points[1].0, "temp");
+        assert!((points[1].1 + 21.5).abs() < 1e-9);
+        assert_eq!(points[2], ("count".to_string(), 70000.0));
+        assert_eq!(points[3], ("volts".to_string(), 3.5));
+        assert_eq!(points[4], ("exact".to_string(), 0.125));
+    }
+
+    #[test]
+    fn test_frame_length() {
+        let schema = BinarySchema::new(vec![BinaryField::new("a", 1, FieldType::I16)]);
+        let parser = BinaryParser::new(schema.clone()).unwrap();
+        assert!(parse(&parser, &[0, 1]).is_err());
+        assert_eq!(parse(&parser, &[0, 1, 0, 9]).unwrap()[0].1, 1.0);
+
+        let parser = BinaryParser::new(schema.with_length(3)).unwrap();
+        assert!(parse(&parser, &[0, 1, 0, 9]).is_err());
+        assert_eq!(parse(&parser, &[0, 0xff, 0xff]).unwrap()[0].1, -1.0);
+    }
+
+    #[test]
+    fn test_invalid_schema() {
+        let field = BinaryField::new("a", 2, FieldType::U32);
+        assert!(BinaryParser::new(BinarySchema::new(vec![field.clone()]).with_length(4)).is_err());
+        assert!(BinaryParser::new(BinarySchema::new(vec![BinaryField::new(
+            "",
+            0,
+            FieldType::U8
+        )]))
+        .is_err());
+        assert!(BinaryParser::new(BinarySchema::new(vec![field.with_scale(f64::NAN)])).is_err());
+    }
+
+    #[test]
+    fn test_schema_serialization() {
+        let schema: BinarySchema = serde_json::from_str(
+            r#"{"fields":[{"name":"t","offset":0,"type":"i16","endianness":"big","scale":0.1}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            schema.fields[0],
+            BinaryField::new("t", 0, FieldType::I16)
+                .with_endianness(Endianness::Big)
+                .with_scale(0.1)
+        );
+        let config = BinaryParser::new(schema).unwrap().config();
+        let json = serde_json::to_string(&config).unwrap();
+        assert!(json.contains(r#""kind":"binary""#));
+        assert_eq!(serde_json::from_str::<ParserConfig>(&json).unwrap(), config);
+    }
+}
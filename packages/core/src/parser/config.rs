@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+
+use super::{
+    BinaryParser, BinarySchema, DelimitedParser, JsonParser, MatchMode, NmeaParser, Parse, Parser,
+    Pipeline,
+};
+use crate::Result;
+
+/// Serializable description of a parser, from which it can be rebuilt
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ParserConfig {
+    Regex {
+        pattern: String,
+        label: String,
+        value: String,
+        #[serde(default)]
+        match_mode: MatchMode,
+        /// Extra (label expr, value expr) pairs
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        fields: Vec<(String, String)>,
+    },
+    Json {
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        include: Vec<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        exclude: Vec<String>,
+    },
+    Delimited {
+        #[serde(default = "default_delimiter")]
+        delimiter: char,
+        /// Fixed column names; learned from a header line when empty
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        columns: Vec<String>,
+    },
+    Binary(BinarySchema),
+    Nmea {
+        #[serde(default = "default_require_checksum")]
+        require_checksum: bool,
+    },
+    Pipeline {
+        parsers: Vec<ParserConfig>,
+    },
+}
+
+fn default_delimiter() -> char {
+    ','
+}
+
+fn default_require_checksum() -> bool {
+    true
+}
+
+impl ParserConfig {
+    /// Build the parser, validating the configuration
+    pub fn build(&self) -> Result<Box<dyn Parse>> {
+        Ok(match self {
+            ParserConfig::Regex {
+                pattern,
+                label,
+                value,
+                match_mode,
+                fields,
+            } => {
+                let mut parser = Parser::new(pattern, label, value)?.with_match_mode(*match_mode);
+                parser.set_extra_fields(fields.clone())?;
+                Box::new(parser)
+            }
+            ParserConfig::Json { include, exclude } => Box::new(
+                JsonParser::new()
+                    .with_include(include.iter().cloned())
+                    .with_exclude(exclude.iter().cloned()),
+            ),
+            ParserConfig::Delimited { delimiter, columns } => {
+                Box::new(DelimitedParser::new(*delimiter).with_columns(columns.iter().cloned()))
+            }
+            ParserConfig::Binary(schema) => Box::new(BinaryParser::new(schema.clone())?),
+            ParserConfig::Nmea { require_checksum } => {
+                Box::new(NmeaParser::new().with_checksum_required(*require_checksum))
+            }
+            ParserConfig::Pipeline { parsers } => {
+                let mut pipeline = Pipeline::new();
+                for config in parsers {
+                    pipeline.push(config.build()?);
+                }
+                Box::new(pipeline)
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Direction, Message, Timestamp};
+
+    #[test]
+    fn test_round_trip() {
+        let parser = Parser::new(r"t=(\S+) h=(\S+)", "t", "$1")
+            .unwrap()
+            .with_field("h", "$2")
+            .unwrap()
+            .with_match_mode(MatchMode::All);
+        let config = Pipeline::new()
+            .with(Box::new(parser))
+            .with(Box::new(JsonParser::new().with_exclude(["id"])))
+            .with(Box::new(DelimitedParser::new(';')))
+            .config();
+
+        let json = serde_json::to_string(&config).unwrap();
+        let restored: ParserConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, config);
+
+        let rebuilt = restored.build().unwrap();
+        assert_eq!(rebuilt.config(), config);
+        let message = Message::new(Timestamp(0), Direction::In, "t=1 h=2");
+        assert_eq!(rebuilt.parse_message(&message).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_defaults_and_validation() {
+        let config: ParserConfig =
+            serde_json::from_str(r#"{"kind":"regex","pattern":"(\\d+)","label":"","value":"$1"}"#)
+                .unwrap();
+        assert!(config.build().is_ok());
+
+        let config: ParserConfig =
+            serde_json::from_str(r#"{"kind":"regex","pattern":"(","label":"","value":"$1"}"#)
+                .unwrap();
+        assert!(config.build().is_err());
+    }
+}
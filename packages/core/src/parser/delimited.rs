@@ -0,0 +1,229 @@
+use std::cell::RefCell;
+
+use super::{Parse, ParserConfig};
+use crate::data::{Message, Point};
+use crate::Result;
+
+/// Parses delimiter-separated columns, e.g. Arduino `a,b,c` output, into one
+/// labelled point per numeric column.
+///
+/// A line of two or more columns, none of them numeric, is a candidate
+/// header. It names the columns once a numeric line with the same number of
+/// columns follows, and replaces any earlier header then (e.g. after a
+/// device reset or a firmware change). Until then a newer candidate replaces
+/// it, so chatter like `Error: busy, retrying` before the real header is
+/// forgotten. Lines without numbers are never points. Columns without a
+/// name are labelled `col0..colN`.
+#[derive(Debug, Clone)]
+pub struct DelimitedParser {
+    delimiter: char,
+    /// Fixed column names; when set, header lines are skipped, not learned
+    columns: Vec<String>,
+    learned: RefCell<Vec<String>>,
+    /// Latest candidate header, not yet confirmed by a numeric line
+    candidate: RefCell<Option<Vec<String>>>,
+}
+
+impl Default for DelimitedParser {
+    fn default() -> Self {
+        Self::new(',')
+    }
+}
+
+impl DelimitedParser {
+    pub fn new(delimiter: char) -> Self {
+        Self {
+            delimiter,
+            columns: Vec::new(),
+            learned: RefCell::new(Vec::new()),
+            candidate: RefCell::new(None),
+        }
+    }
+
+    /// Use these column names instead of learning them from a header
+    pub fn with_columns(mut self, columns: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.columns = columns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn delimiter(&self) -> char {
+        self.delimiter
+    }
+
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    /// Column names currently in effect, fixed or learned
+    pub fn header(&self) -> Vec<String> {
+        if self.columns.is_empty() {
+            self.learned.borrow().clone()
+        } else {
+            self.columns.clone()
+        }
+    }
+
+    fn label(&self, names: &[String], index: usize) -> String {
+        match names.get(index) {
+            Some(name) if !name.is_empty() => name.clone(),
+            _ => format!("col{}", index),
+        }
+    }
+}
+
+impl Parse for DelimitedParser {
+    fn parse_message(&self, message: &Message) -> Result<Vec<Point>> {
+        let text = message.text().trim();
+        if text.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let cells: Vec<&str> = text.split(self.delimiter).map(str::trim).collect();
+        let values: Vec<Option<f64>> = cells.iter().map(|cell| cell.parse().ok()).collect();
+
+        if values.iter().all(Option::is_none) {
+            if self.columns.is_empty() && cells.len() >= 2 {
+                *self.candidate.borrow_mut() = Some(cells.iter().map(|c| c.to_string()).collect());
+            }
+            return Ok(Vec::new());
+        }
+
+        // A numeric line confirms a candidate of its width; one of another
+        // width was not a header
+        if let Some(candidate) = self.candidate.borrow_mut().take() {
+            if candidate.len() == cells.len() {
+                *self.learned.borrow_mut() = candidate;
+            }
+        }
+
+        let names = self.header();
+        Ok(values
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, value)| {
+                value.map(|v| Point::new(message.timestamp(), v).with_label(self.label(&names, i)))
+            })
+            .collect())
+    }
+
+    fn description(&self) -> String {
+        let delimiter = match self.delimiter {
+            '\t' => "\\t".to_string(),
+            c => c.to_string(),
+        };
+        if self.columns.is_empty() {
+            format!("Delimited '{}'", delimiter)
+        } else {
+            format!("Delimited '{}' {}", delimiter, self.columns.join(", "))
+        }
+    }
+
+    fn config(&self) -> ParserConfig {
+        ParserConfig::Delimited {
+            delimiter: self.delimiter,
+            columns: self.columns.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Direction, Timestamp};
+
+    fn parse(parser: &DelimitedParser, text: &str) -> Vec<(String, f64)> {
+        let message = Message::new(Timestamp(7), Direction::In, text);
+        parser
+            .parse_message(&message)
+            .unwrap()
+            .into_iter()
+            .map(|p| {
+                assert_eq!(p.timestamp(), Timestamp(7));
+                (p.label().unwrap().to_string(), p.value())
+            })
+            .collect()
+    }
+
+    fn owned(points: &[(&str, f64)]) -> Vec<(String, f64)> {
+        points.iter().map(|(l, v)| (l.to_string(), *v)).collect()
+    }
+
+    #[test]
+    fn test_without_header() {
+        let parser = DelimitedParser::default();
+        assert_eq!(
+            parse(&parser, "1.5, -2,3\r"),
+            owned(&[("col0", 1.5), ("col1", -2.0), ("col2", 3.0)])
+        );
+    }
+
+    #[test]
+    fn test_learns_header() {
+        let parser = DelimitedParser::new('\t');
+        assert!(parse(&parser, "temp\t\thum").is_empty());
+        assert_eq!(
+            parse(&parser, "21.5\t7\t40"),
+            owned(&[("temp", 21.5), ("col1", 7.0), ("hum", 40.0)])
+        );
+
+        // A new header replaces the old one, even with other columns
+        assert!(parse(&parser, "a\tb").is_empty());
+        assert_eq!(parse(&parser, "1\t2"), owned(&[("a", 1.0), ("b", 2.0)]));
+    }
+
+    #[test]
+    fn test_chatter_before_header() {
+        let parser = DelimitedParser::default();
+        assert!(parse(&parser, "Error: sensor busy, retrying").is_empty());
+        assert!(parse(&parser, "temp,hum,pres").is_empty());
+        assert_eq!(
+            parse(&parser, "21.5,40,1013"),
+            owned(&[("temp", 21.5), ("hum", 40.0), ("pres", 1013.0)])
+        );
+
+        // An unconfirmed candidate is not used, and rows of another width
+        // discard it
+        assert!(parse(&parser, "Warning: low battery, 10%left").is_empty());
+        assert_eq!(
+            parse(&parser, "21.6,41,1012"),
+            owned(&[("temp", 21.6), ("hum", 41.0), ("pres", 1012.0)])
+        );
+        assert_eq!(parser.header(), vec!["temp", "hum", "pres"]);
+    }
+
+    #[test]
+    fn test_ignores_chatter() {
+        let parser = DelimitedParser::default();
+        assert!(parse(&parser, "Booting...").is_empty());
+        assert!(parse(&parser, "temp,hum").is_empty());
+        assert!(parse(&parser, "OK").is_empty());
+        assert_eq!(
+            parse(&parser, "21.5,40"),
+            owned(&[("temp", 21.5), ("hum", 40.0)])
+        );
+
+        // Neither a single word nor a sentence with other commas is a header
+        assert!(parse(&parser, "Ready").is_empty());
+        assert!(parse(&parser, "Error: sensor busy, retrying, please wait").is_empty());
+        assert_eq!(
+            parse(&parser, "21.7,41"),
+            owned(&[("temp", 21.7), ("hum", 41.0)])
+        );
+        assert_eq!(parser.header(), vec!["temp", "hum"]);
+    }
+
+    #[test]
+    fn test_skips_non_numeric_columns() {
+        let parser = DelimitedParser::default();
+        parse(&parser, "state,temp");
+        assert_eq!(parse(&parser, "ok,21.5"), owned(&[("temp", 21.5)]));
+        assert!(parse(&parser, "").is_empty());
+    }
+
+    #[test]
+    fn test_fixed_columns() {
+        let parser = DelimitedParser::new(';').with_columns(["x", "y"]);
+        assert!(parse(&parser, "a;b").is_empty());
+        assert_eq!(parse(&parser, "1;2"), owned(&[("x", 1.0), ("y", 2.0)]));
+    }
+}
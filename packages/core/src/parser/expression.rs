@@ -1,57 +1,173 @@
 use super::regex::ExtractionResult;
-use crate::Result;
+use crate::{Error, Result};
+use ::regex::Regex;
+use std::collections::HashMap;
 use std::fmt;
 
 /// Represents a parsed expression
-/// TODO: Extend with binary operations, function calls, etc.
-#[derive(Debug, Clone)]
+///
+/// Grammar, loosest binding first:
+///
+/// ```text
+/// expr    := sum (("==" | "!=" | "<" | "<=" | ">" | ">=") sum)*
+/// sum     := product (("+" | "-") product)*
+/// product := unary (("*" | "/" | "%") unary)*
+/// unary   := ("-" | "+") unary | power
+/// power   := primary ("^" unary)?
+/// primary := number | "string" | $n | ${n} | ${name} | func "(" args ")" | "(" expr ")"
+/// ```
+#[derive(Debug, Clone, PartialEq)]
 pub enum Expr {
     /// A reference to a capture group by index
     Ref(usize),
+    /// A reference to a named capture group, written `${name}`
+    Named(String),
     /// A numeric value
     Value(f64),
     /// A string value
     Str(String),
+    /// A prefix operation
+    Unary(UnaryOp, Box<Expr>),
+    /// An infix operation
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    /// A built-in function call
+    Call(Function, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Neg,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Pow,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// Built-in functions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Function {
+    Abs,
+    Min,
+    Max,
+    /// `round(x)` or `round(x, digits)`
+    Round,
+    Floor,
+    Ceil,
+    Sqrt,
+    /// Natural log `log(x)` or `log(x, base)`
+    Log,
+    Log10,
+    Exp,
+    /// Interpret the argument text as a hexadecimal integer
+    Hex,
 }
 
 impl Expr {
-    /// Parse a string expression into an Expr
-    /// Supports direct $n references and numeric/string literals.
+    /// Parse a string expression into an Expr.
+    /// Input that is not a valid expression is kept as a string literal, so
+    /// plain labels like `Temperature` keep working.
     pub fn new(expr_str: &str) -> Self {
-        let trimmed = expr_str.trim();
+        Self::parse(expr_str).unwrap_or_else(|_| Expr::Str(expr_str.trim().to_string()))
+    }
 
-        // Try to parse as a capture reference like $1
-        if let Some(idx) = parse_capture_reference(trimmed) {
-            return Expr::Ref(idx);
+    /// Parse a string expression, failing on invalid syntax
+    pub fn parse(expr_str: &str) -> Result<Self> {
+        let tokens = tokenize(expr_str)?;
+        let mut parser = ExprParser { tokens, pos: 0 };
+        let expr = parser.expr()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some(token) => Err(Error::ParseError(format!(
+                "Unexpected '{}' in expression",
+                token
+            ))),
         }
+    }
 
-        // Try to parse as a numeric literal
-        if let Ok(num) = trimmed.parse::<f64>() {
-            return Expr::Value(num);
+    /// Evaluate the expression against extracted captures.
+    /// The result is always `Expr::Value` or `Expr::Str`.
+    pub fn eval(&self, extraction: &ExtractionResult) -> Result<Self> {
+        match self {
+            Expr::Ref(idx) => {
+                let value = get_capture_value(&extraction.captures, *idx)?;
+                Ok(value
+                    .parse::<f64>() // Try parse as a number
+                    .map(Expr::Value)
+                    .unwrap_or(Expr::Str(value))) // Fallback to string
+            }
+            Expr::Named(name) => {
+                let value = get_named_value(&extraction.named, name)?;
+                Ok(value
+                    .parse::<f64>()
+                    .map(Expr::Value)
+                    .unwrap_or(Expr::Str(value)))
+            }
+            Expr::Value(_) | Expr::Str(_) => Ok(self.clone()),
+            Expr::Unary(UnaryOp::Neg, operand) => {
+                let value: f64 = operand.eval(extraction)?.try_into()?;
+                Ok(Expr::Value(-value))
+            }
+            Expr::Binary(op, lhs, rhs) => {
+                eval_binary(*op, lhs.eval(extraction)?, rhs.eval(extraction)?)
+            }
+            Expr::Call(Function::Hex, args) => {
+                let text = args[0].eval_text(extraction)?;
+                parse_hex(&text).map(Expr::Value)
+            }
+            Expr::Call(function, args) => {
+                let values = args
+                    .iter()
+                    .map(|arg| f64::try_from(arg.eval(extraction)?))
+                    .collect::<Result<Vec<f64>>>()?;
+                Ok(Expr::Value(eval_function(*function, &values)))
+            }
         }
+    }
 
-        // Fall back to string
-        Expr::Str(trimmed.to_string())
+    /// Evaluate to text, taking capture references verbatim rather than
+    /// round-tripping them through f64
+    fn eval_text(&self, extraction: &ExtractionResult) -> Result<String> {
+        match self {
+            Expr::Ref(idx) => get_capture_value(&extraction.captures, *idx),
+            Expr::Named(name) => get_named_value(&extraction.named, name),
+            other => other.eval(extraction)?.try_into(),
+        }
     }
 
-    /// Evaluate the expression
-    /// Currently returns self as-is since we don't have operations yet.
-    /// TODO: Implement evaluation for binary operations, function calls, etc.
-    pub fn eval(self, extraction: &ExtractionResult) -> Result<Self> {
+    /// Check that every capture group the expression refers to exists in
+    /// `regex`, so a bad reference is a configuration error rather than a
+    /// failure on every message
+    pub fn check_captures(&self, regex: &Regex) -> Result<()> {
         match self {
-            Expr::Ref(idx) => {
-                if let Ok(value) = get_capture_value(&extraction.captures, idx) {
-                    return value
-                        .parse::<f64>() // Try parse as a number
-                        .map(Expr::Value)
-                        .or_else(|_| Ok(Expr::Str(value))); // Fallback to string
-                }
-                Err(crate::Error::ParseError(format!(
-                    "Cannot resolve capture reference ${}",
-                    idx
+            Expr::Ref(idx) if *idx >= regex.captures_len() => Err(Error::ConfigError(format!(
+                "Capture group ${} not found in the pattern",
+                idx
+            ))),
+            Expr::Named(name) if !regex.capture_names().flatten().any(|n| n == name) => {
+                Err(Error::ConfigError(format!(
+                    "Capture group ${{{}}} not found in the pattern",
+                    name
                 )))
             }
-            other => Ok(other),
+            Expr::Unary(_, operand) => operand.check_captures(regex),
+            Expr::Binary(_, lhs, rhs) => {
+                lhs.check_captures(regex)?;
+                rhs.check_captures(regex)
+            }
+            Expr::Call(_, args) => args.iter().try_for_each(|arg| arg.check_captures(regex)),
+            Expr::Ref(_) | Expr::Named(_) | Expr::Value(_) | Expr::Str(_) => Ok(()),
         }
     }
 }
@@ -60,24 +176,111 @@ impl fmt::Display for Expr {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Expr::Ref(idx) => write!(f, "<ref: {}>", idx),
+            Expr::Named(name) => write!(f, "<ref: {}>", name),
             Expr::Value(num) => write!(f, "{}", num),
             Expr::Str(s) => write!(f, "{}", s),
+            Expr::Unary(UnaryOp::Neg, operand) => write!(f, "(-{})", operand),
+            Expr::Binary(op, lhs, rhs) => write!(f, "({} {} {})", lhs, op, rhs),
+            Expr::Call(function, args) => {
+                write!(f, "{}(", function)?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", arg)?;
+                }
+                write!(f, ")")
+            }
         }
     }
 }
 
+impl fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Rem => "%",
+            BinaryOp::Pow => "^",
+            BinaryOp::Eq => "==",
+            BinaryOp::Ne => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::Le => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Ge => ">=",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        let function = match name {
+            "abs" => Function::Abs,
+            "min" => Function::Min,
+            "max" => Function::Max,
+            "round" => Function::Round,
+            "floor" => Function::Floor,
+            "ceil" => Function::Ceil,
+            "sqrt" => Function::Sqrt,
+            "log" => Function::Log,
+            "log10" => Function::Log10,
+            "exp" => Function::Exp,
+            "hex" => Function::Hex,
+            _ => return None,
+        };
+        Some(function)
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Function::Abs => "abs",
+            Function::Min => "min",
+            Function::Max => "max",
+            Function::Round => "round",
+            Function::Floor => "floor",
+            Function::Ceil => "ceil",
+            Function::Sqrt => "sqrt",
+            Function::Log => "log",
+            Function::Log10 => "log10",
+            Function::Exp => "exp",
+            Function::Hex => "hex",
+        }
+    }
+
+    /// Accepted argument count range (inclusive)
+    fn arity(&self) -> (usize, usize) {
+        match self {
+            Function::Min | Function::Max => (1, usize::MAX),
+            Function::Round | Function::Log => (1, 2),
+            _ => (1, 1),
+        }
+    }
+}
+
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
 impl TryFrom<&Expr> for f64 {
     type Error = crate::Error;
 
     fn try_from(expr: &Expr) -> Result<Self> {
         match expr {
             Expr::Value(num) => Ok(*num),
-            Expr::Ref(_) => Err(crate::Error::ParseError(
-                "Cannot convert reference to f64 directly".to_string(),
-            )),
             Expr::Str(s) => s
                 .parse::<f64>()
                 .map_err(|_| crate::Error::ParseError(format!("Cannot convert '{}' to f64", s))),
+            Expr::Ref(_) | Expr::Named(_) => Err(crate::Error::ParseError(
+                "Cannot convert reference to f64 directly".to_string(),
+            )),
+            _ => Err(crate::Error::ParseError(
+                "Cannot convert unevaluated expression to f64".to_string(),
+            )),
         }
     }
 }
@@ -94,12 +297,15 @@ impl TryFrom<&Expr> for String {
     type Error = crate::Error;
 
     fn try_from(expr: &Expr) -> Result<Self> {
-        if let Expr::Ref(_) = expr {
-            return Err(crate::Error::ParseError(
+        match expr {
+            Expr::Value(_) | Expr::Str(_) => Ok(expr.to_string()),
+            Expr::Ref(_) | Expr::Named(_) => Err(crate::Error::ParseError(
                 "Cannot convert reference to String directly".to_string(),
-            ));
+            )),
+            _ => Err(crate::Error::ParseError(
+                "Cannot convert unevaluated expression to String".to_string(),
+            )),
         }
-        Ok(expr.to_string())
     }
 }
 
@@ -111,15 +317,6 @@ impl TryFrom<Expr> for String {
     }
 }
 
-/// Parse a simple capture reference like "$1" or "$5"
-fn parse_capture_reference(s: &str) -> Option<usize> {
-    if s.starts_with('$') && s.len() > 1 {
-        s[1..].parse::<usize>().ok()
-    } else {
-        None
-    }
-}
-
 /// Get a capture value by index
 fn get_capture_value(captures: &[String], index: usize) -> Result<String> {
     captures
@@ -128,7 +325,411 @@ fn get_capture_value(captures: &[String], index: usize) -> Result<String> {
         .ok_or_else(|| crate::Error::ParseError(format!("Capture group ${} not found", index)))
 }
 
-// TODO: Implement arithmetic expression evaluator (+, -, *, /)
+/// Get a named capture value
+fn get_named_value(named: &HashMap<String, String>, name: &str) -> Result<String> {
+    named
+        .get(name)
+        .cloned()
+        .ok_or_else(|| crate::Error::ParseError(format!("Capture group ${{{}}} not found", name)))
+}
+
+fn eval_binary(op: BinaryOp, lhs: Expr, rhs: Expr) -> Result<Expr> {
+    let numbers = (f64::try_from(&lhs), f64::try_from(&rhs));
+
+    // `+` on non-numeric operands concatenates, e.g. "ch" + $1
+    if op == BinaryOp::Add {
+        if let (Ok(a), Ok(b)) = numbers {
+            return Ok(Expr::Value(a + b));
+        }
+        return Ok(Expr::Str(format!("{}{}", lhs, rhs)));
+    }
+
+    if matches!(
+        op,
+        BinaryOp::Eq | BinaryOp::Ne | BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge
+    ) {
+        let ordering = match numbers {
+            (Ok(a), Ok(b)) => a.partial_cmp(&b),
+            _ => Some(lhs.to_string().cmp(&rhs.to_string())),
+        };
+        let result = ordering.is_some_and(|ordering| match op {
+            BinaryOp::Eq => ordering.is_eq(),
+            BinaryOp::Ne => ordering.is_ne(),
+            BinaryOp::Lt => ordering.is_lt(),
+            BinaryOp::Le => ordering.is_le(),
+            BinaryOp::Gt => ordering.is_gt(),
+            _ => ordering.is_ge(),
+        });
+        return Ok(Expr::Value(if result { 1.0 } else { 0.0 }));
+    }
+
+    let a = numbers.0?;
+    let b = numbers.1?;
+    let value = match op {
+        BinaryOp::Sub => a - b,
+        BinaryOp::Mul => a * b,
+        BinaryOp::Div => a / b,
+        BinaryOp::Rem => a % b,
+        _ => a.powf(b),
+    };
+    Ok(Expr::Value(value))
+}
+
+fn eval_function(function: Function, args: &[f64]) -> f64 {
+    match function {
+        Function::Abs => args[0].abs(),
+        Function::Min => args.iter().copied().fold(f64::INFINITY, f64::min),
+        Function::Max => args.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        Function::Round => match args.get(1) {
+            Some(digits) => {
+                let factor = 10f64.powi(*digits as i32);
+                (args[0] * factor).round() / factor
+            }
+            None => args[0].round(),
+        },
+        Function::Floor => args[0].floor(),
+        Function::Ceil => args[0].ceil(),
+        Function::Sqrt => args[0].sqrt(),
+        Function::Log => match args.get(1) {
+            Some(base) => args[0].log(*base),
+            None => args[0].ln(),
+        },
+        Function::Log10 => args[0].log10(),
+        Function::Exp => args[0].exp(),
+        // Evaluated from text in `Expr::eval`
+        Function::Hex => unreachable!("hex() is evaluated from text"),
+    }
+}
+
+/// Parse hexadecimal text such as `1F`, `0x1f` or `-0x10`
+fn parse_hex(text: &str) -> Result<f64> {
+    let trimmed = text.trim();
+    let (negative, digits) = match trimmed.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, trimmed),
+    };
+    let digits = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+        .unwrap_or(digits);
+    let value = u64::from_str_radix(digits, 16)
+        .map_err(|_| Error::ParseError(format!("Cannot parse '{}' as hex", text)))?
+        as f64;
+    Ok(if negative { -value } else { value })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Str(String),
+    Ref(usize),
+    Named(String),
+    Ident(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+    Comma,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "{}", n),
+            Token::Str(s) => write!(f, "\"{}\"", s),
+            Token::Ref(idx) => write!(f, "${}", idx),
+            Token::Named(name) => write!(f, "${{{}}}", name),
+            Token::Ident(name) => write!(f, "{}", name),
+            Token::Op(op) => write!(f, "{}", op),
+            Token::LParen => write!(f, "("),
+            Token::RParen => write!(f, ")"),
+            Token::Comma => write!(f, ","),
+        }
+    }
+}
+
+const OPERATORS: [&str; 12] = [
+    "==", "!=", "<=", ">=", "<", ">", "+", "-", "*", "/", "%", "^",
+];
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        let token = match c {
+            '(' => {
+                i += 1;
+                Token::LParen
+            }
+            ')' => {
+                i += 1;
+                Token::RParen
+            }
+            ',' => {
+                i += 1;
+                Token::Comma
+            }
+            '$' if chars.get(i + 1) == Some(&'{') => {
+                i += 2;
+                while i < chars.len() && chars[i] != '}' {
+                    i += 1;
+                }
+                if i == chars.len() {
+                    return Err(Error::ParseError(format!(
+                        "Unterminated capture reference at position {}",
+                        start
+                    )));
+                }
+                let name: String = chars[start + 2..i].iter().collect();
+                i += 1;
+                let name = name.trim();
+                if name.is_empty() {
+                    return Err(Error::ParseError(format!(
+                        "Empty capture reference at position {}",
+                        start
+                    )));
+                }
+                // ${1} is the same as $1
+                match name.parse::<usize>() {
+                    Ok(idx) => Token::Ref(idx),
+                    Err(_) => Token::Named(name.to_string()),
+                }
+            }
+            '$' => {
+                i += 1;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+                let digits: String = chars[start + 1..i].iter().collect();
+                let idx = digits.parse::<usize>().map_err(|_| {
+                    Error::ParseError(format!("Invalid capture reference at position {}", start))
+                })?;
+                Token::Ref(idx)
+            }
+            '"' | '\'' => {
+                i += 1;
+                while i < chars.len() && chars[i] != c {
+                    i += 1;
+                }
+                if i == chars.len() {
+                    return Err(Error::ParseError(format!(
+                        "Unterminated string starting at position {}",
+                        start
+                    )));
+                }
+                i += 1;
+                Token::Str(chars[start + 1..i - 1].iter().collect())
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '.') {
+                    // Allow a signed exponent such as 1e-3
+                    let exponent_sign = matches!(chars[i], 'e' | 'E')
+                        && matches!(chars.get(i + 1), Some('+' | '-'));
+                    i += if exponent_sign { 2 } else { 1 };
+                }
+                let text: String = chars[start..i].iter().collect();
+                let number = match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+                    Some(_) => parse_hex(&text).ok(),
+                    None => text.parse::<f64>().ok(),
+                };
+                Token::Number(number.ok_or_else(|| {
+                    Error::ParseError(format!("Invalid number '{}' at position {}", text, start))
+                })?)
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                Token::Ident(chars[start..i].iter().collect())
+            }
+            _ => {
+                let rest: String = chars[i..].iter().take(2).collect();
+                let op = OPERATORS
+                    .iter()
+                    .find(|op| rest.starts_with(**op))
+                    .ok_or_else(|| {
+                        Error::ParseError(format!(
+                            "Unexpected character '{}' at position {}",
+                            c, start
+                        ))
+                    })?;
+                i += op.len();
+                Token::Op(op)
+            }
+        };
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent parser over the token stream
+struct ExprParser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl ExprParser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    /// Consume the next token if it is one of `ops`
+    fn eat_op(&mut self, ops: &[&str]) -> Option<&'static str> {
+        match self.peek() {
+            Some(Token::Op(op)) if ops.contains(op) => {
+                let op = *op;
+                self.pos += 1;
+                Some(op)
+            }
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<()> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(Error::ParseError(format!(
+                "Expected '{}' but found '{}'",
+                expected, token
+            ))),
+            None => Err(Error::ParseError(format!(
+                "Expected '{}' but reached end of expression",
+                expected
+            ))),
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr> {
+        let mut lhs = self.sum()?;
+        while let Some(op) = self.eat_op(&["==", "!=", "<", "<=", ">", ">="]) {
+            let op = match op {
+                "==" => BinaryOp::Eq,
+                "!=" => BinaryOp::Ne,
+                "<" => BinaryOp::Lt,
+                "<=" => BinaryOp::Le,
+                ">" => BinaryOp::Gt,
+                _ => BinaryOp::Ge,
+            };
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.sum()?));
+        }
+        Ok(lhs)
+    }
+
+    fn sum(&mut self) -> Result<Expr> {
+        let mut lhs = self.product()?;
+        while let Some(op) = self.eat_op(&["+", "-"]) {
+            let op = if op == "+" {
+                BinaryOp::Add
+            } else {
+                BinaryOp::Sub
+            };
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.product()?));
+        }
+        Ok(lhs)
+    }
+
+    fn product(&mut self) -> Result<Expr> {
+        let mut lhs = self.unary()?;
+        while let Some(op) = self.eat_op(&["*", "/", "%"]) {
+            let op = match op {
+                "*" => BinaryOp::Mul,
+                "/" => BinaryOp::Div,
+                _ => BinaryOp::Rem,
+            };
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.unary()?));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        match self.eat_op(&["-", "+"]) {
+            Some("-") => Ok(Expr::Unary(UnaryOp::Neg, Box::new(self.unary()?))),
+            Some(_) => self.unary(),
+            None => self.power(),
+        }
+    }
+
+    fn power(&mut self) -> Result<Expr> {
+        let base = self.primary()?;
+        if self.eat_op(&["^"]).is_some() {
+            // Right-associative: 2^3^2 == 2^(3^2)
+            let exponent = self.unary()?;
+            return Ok(Expr::Binary(
+                BinaryOp::Pow,
+                Box::new(base),
+                Box::new(exponent),
+            ));
+        }
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Expr::Value(n)),
+            Some(Token::Str(s)) => Ok(Expr::Str(s)),
+            Some(Token::Ref(idx)) => Ok(Expr::Ref(idx)),
+            Some(Token::Named(name)) => Ok(Expr::Named(name)),
+            Some(Token::LParen) => {
+                let expr = self.expr()?;
+                self.expect(Token::RParen)?;
+                Ok(expr)
+            }
+            Some(Token::Ident(name)) => {
+                let function = Function::from_name(&name)
+                    .ok_or_else(|| Error::ParseError(format!("Unknown function '{}'", name)))?;
+                self.expect(Token::LParen)?;
+                let mut args = Vec::new();
+                if self.peek() != Some(&Token::RParen) {
+                    args.push(self.expr()?);
+                    while self.peek() == Some(&Token::Comma) {
+                        self.pos += 1;
+                        args.push(self.expr()?);
+                    }
+                }
+                self.expect(Token::RParen)?;
+
+                let (min, max) = function.arity();
+                if args.len() < min || args.len() > max {
+                    return Err(Error::ParseError(format!(
+                        "{}() takes {} argument(s), got {}",
+                        function,
+                        if min == max {
+                            min.to_string()
+                        } else if max == usize::MAX {
+                            format!("at least {}", min)
+                        } else {
+                            format!("{}-{}", min, max)
+                        },
+                        args.len()
+                    )));
+                }
+                Ok(Expr::Call(function, args))
+            }
+            Some(token) => Err(Error::ParseError(format!(
+                "Unexpected '{}' in expression",
+                token
+            ))),
+            None => Err(Error::ParseError(
+                "Unexpected end of expression".to_string(),
+            )),
+        }
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -138,6 +739,7 @@ mod tests {
     fn test_parse_capture_reference() {
         let extraction = ExtractionResult {
             captures: vec!["full".to_string(), "42.5".to_string()],
+            ..Default::default()
         };
         let expr = Expr::new("$1").eval(&extraction).unwrap();
         match expr {
@@ -150,6 +752,7 @@ mod tests {
     fn test_parse_string_reference() {
         let extraction = ExtractionResult {
             captures: vec!["full".to_string(), "hello".to_string()],
+            ..Default::default()
         };
         let expr = Expr::new("$1").eval(&extraction).unwrap();
         match expr {
@@ -160,7 +763,7 @@ mod tests {
 
     #[test]
     fn test_parse_numeric_literal() {
-        let extraction = ExtractionResult { captures: vec![] };
+        let extraction = ExtractionResult::default();
         let expr = Expr::new("42.5").eval(&extraction).unwrap();
         match expr {
             Expr::Value(num) => assert_eq!(num, 42.5),
@@ -170,7 +773,7 @@ mod tests {
 
     #[test]
     fn test_parse_string_literal() {
-        let extraction = ExtractionResult { captures: vec![] };
+        let extraction = ExtractionResult::default();
         let expr = Expr::new("hello").eval(&extraction).unwrap();
         match expr {
             Expr::Str(s) => assert_eq!(s, "hello"),
@@ -201,8 +804,147 @@ mod tests {
     fn test_invalid_capture_reference() {
         let extraction = ExtractionResult {
             captures: vec!["full".to_string(), "42".to_string()],
+            ..Default::default()
         };
         let result = Expr::new("$5").eval(&extraction);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_check_captures() {
+        let regex = Regex::new(r"(?<temp>\d+) (\d+)").unwrap();
+        assert!(Expr::new("$0 + $2 * ${temp}")
+            .check_captures(&regex)
+            .is_ok());
+        assert!(Expr::new("$3").check_captures(&regex).is_err());
+        assert!(Expr::new("round(-${hum})").check_captures(&regex).is_err());
+    }
+
+    fn eval_num(expr: &str, captures: &[&str]) -> f64 {
+        let extraction = ExtractionResult {
+            captures: captures.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        };
+        Expr::parse(expr)
+            .unwrap()
+            .eval(&extraction)
+            .unwrap()
+            .try_into()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_arithmetic_precedence() {
+        assert_eq!(eval_num("1 + 2 * 3", &[]), 7.0);
+        assert_eq!(eval_num("(1 + 2) * 3", &[]), 9.0);
+        assert_eq!(eval_num("10 - 4 - 3", &[]), 3.0);
+        assert_eq!(eval_num("7 % 4", &[]), 3.0);
+        assert_eq!(eval_num("2 ^ 3 ^ 2", &[]), 512.0);
+        assert_eq!(eval_num("-2 ^ 2", &[]), -4.0);
+        assert_eq!(eval_num("2 ^ -1", &[]), 0.5);
+        assert_eq!(eval_num("--3", &[]), 3.0);
+    }
+
+    #[test]
+    fn test_adc_conversion() {
+        let value = eval_num("($2 * 3.3 / 4095) - 0.5", &["full", "ch", "4095"]);
+        assert!((value - 2.8).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_comparisons() {
+        assert_eq!(eval_num("$1 > 10", &["", "11"]), 1.0);
+        assert_eq!(eval_num("$1 <= 10", &["", "11"]), 0.0);
+        assert_eq!(eval_num("1 + 1 == 2", &[]), 1.0);
+        assert_eq!(eval_num("3 != 3", &[]), 0.0);
+    }
+
+    #[test]
+    fn test_functions() {
+        assert_eq!(eval_num("abs(-2.5)", &[]), 2.5);
+        assert_eq!(eval_num("min(3, 1, 2)", &[]), 1.0);
+        assert_eq!(eval_num("max(3, 1, 2)", &[]), 3.0);
+        assert_eq!(eval_num("round(2.567, 2)", &[]), 2.57);
+        assert_eq!(eval_num("round(2.5)", &[]), 3.0);
+        assert_eq!(eval_num("sqrt(16)", &[]), 4.0);
+        assert_eq!(eval_num("log(exp(2))", &[]), 2.0);
+        assert_eq!(eval_num("log(8, 2)", &[]), 3.0);
+        assert_eq!(eval_num("log10(1000)", &[]), 3.0);
+        assert_eq!(eval_num("floor(1.7) + ceil(1.2)", &[]), 3.0);
+    }
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(eval_num("hex($1)", &["", "1F"]), 31.0);
+        assert_eq!(eval_num("hex($1)", &["", "0x10"]), 16.0);
+        // Digits-only captures are read as hex, not decimal
+        assert_eq!(eval_num("hex($1) * 2", &["", "10"]), 32.0);
+        assert_eq!(eval_num("0xFF", &[]), 255.0);
+    }
+
+    #[test]
+    fn test_string_concatenation() {
+        let extraction = ExtractionResult {
+            captures: vec!["".to_string(), "3".to_string()],
+            ..Default::default()
+        };
+        let label: String = Expr::parse("\"ch\" + $1")
+            .unwrap()
+            .eval(&extraction)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert_eq!(label, "ch3");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Expr::parse("1 +").is_err());
+        assert!(Expr::parse("(1 + 2").is_err());
+        assert!(Expr::parse("foo(1)").is_err());
+        assert!(Expr::parse("sqrt(1, 2)").is_err());
+        assert!(Expr::parse("1 2").is_err());
+        assert!(Expr::parse("\"open").is_err());
+    }
+
+    #[test]
+    fn test_named_references() {
+        let extraction = ExtractionResult {
+            captures: vec![
+                "temp=21.5".to_string(),
+                "temp".to_string(),
+                "21.5".to_string(),
+            ],
+            named: HashMap::from([
+                ("sensor".to_string(), "temp".to_string()),
+                ("val".to_string(), "21.5".to_string()),
+            ]),
+        };
+        let value: f64 = Expr::parse("${val} * 2")
+            .unwrap()
+            .eval(&extraction)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert_eq!(value, 43.0);
+        let label: String = Expr::new("${sensor}")
+            .eval(&extraction)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert_eq!(label, "temp");
+        // ${n} is positional
+        assert_eq!(Expr::parse("${2}").unwrap(), Expr::Ref(2));
+        assert!(Expr::new("${missing}").eval(&extraction).is_err());
+        assert!(Expr::parse("${val").is_err());
+    }
+
+    #[test]
+    fn test_non_numeric_operand() {
+        let extraction = ExtractionResult {
+            captures: vec!["".to_string(), "abc".to_string()],
+            ..Default::default()
+        };
+        assert!(Expr::new("$1 * 2").eval(&extraction).is_err());
+    }
 }
@@ -0,0 +1,189 @@
+use serde_json::Value;
+
+use super::{Parse, ParserConfig};
+use crate::data::{Message, Point};
+use crate::{Error, Result};
+
+/// Parses one JSON object per message into labelled points.
+///
+/// Every numeric field becomes a point labelled with its dotted path, e.g.
+/// `{"imu":{"accel":{"x":0.1}}}` yields `imu.accel.x`; array elements use
+/// their index (`samples.0`). Non-numeric fields are ignored.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JsonParser {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl JsonParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only emit fields at or below these paths
+    pub fn with_include(mut self, paths: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.include = paths.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Never emit fields at or below these paths
+    pub fn with_exclude(mut self, paths: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.exclude = paths.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn include(&self) -> &[String] {
+        &self.include
+    }
+
+    pub fn exclude(&self) -> &[String] {
+        &self.exclude
+    }
+
+    fn is_selected(&self, path: &str) -> bool {
+        let included =
+            self.include.is_empty() || self.include.iter().any(|p| path_matches(path, p));
+        included && !self.exclude.iter().any(|p| path_matches(path, p))
+    }
+
+    fn collect(&self, prefix: &str, value: &Value, out: &mut Vec<(String, f64)>) {
+        match value {
+            Value::Number(number) => {
+                if let Some(n) = number.as_f64() {
+                    if self.is_selected(prefix) {
+                        out.push((prefix.to_string(), n));
+                    }
+                }
+            }
+            Value::Object(map) => {
+                for (key, child) in map {
+                    self.collect(&join(prefix, key), child, out);
+                }
+            }
+            Value::Array(items) => {
+                for (i, child) in items.iter().enumerate() {
+                    self.collect(&join(prefix, &i.to_string()), child, out);
+                }
+            }
+            Value::Null | Value::Bool(_) | Value::String(_) => {}
+        }
+    }
+}
+
+impl Parse for JsonParser {
+    fn parse_message(&self, message: &Message) -> Result<Vec<Point>> {
+        let value: Value = serde_json::from_slice(message.bytes())
+            .map_err(|e| Error::ParseError(format!("Invalid JSON: {}", e)))?;
+        if !value.is_object() {
+            return Err(Error::ParseError("Expected a JSON object".to_string()));
+        }
+
+        let mut fields = Vec::new();
+        self.collect("", &value, &mut fields);
+        Ok(fields
+            .into_iter()
+            .map(|(label, n)| Point::new(message.timestamp(), n).with_label(label))
+            .collect())
+    }
+
+    fn description(&self) -> String {
+        let mut description = "JSON".to_string();
+        if !self.include.is_empty() {
+            description.push_str(&format!(" include {}", self.include.join(", ")));
+        }
+        if !self.exclude.is_empty() {
+            description.push_str(&format!(" exclude {}", self.exclude.join(", ")));
+        }
+        description
+    }
+
+    fn config(&self) -> ParserConfig {
+        ParserConfig::Json {
+            include: self.include.clone(),
+            exclude: self.exclude.clone(),
+        }
+    }
+}
+
+fn join(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", prefix, key)
+    }
+}
+
+/// `path` equals `pattern` or lies below it (`imu` matches `imu.accel.x`)
+fn path_matches(path: &str, pattern: &str) -> bool {
+    path.strip_prefix(pattern)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Direction, Timestamp};
+
+    fn parse(parser: &JsonParser, text: &str) -> Result<Vec<(String, f64)>> {
+        let message = Message::new(Timestamp::from_millis(5), Direction::In, text);
+        Ok(parser
+            .parse_message(&message)?
+            .into_iter()
+            .map(|p| {
+                assert_eq!(p.timestamp(), Timestamp(5));
+                (p.label().unwrap().to_string(), p.value())
+            })
+            .collect())
+    }
+
+    #[test]
+    fn test_flat_object() {
+        let points = parse(&JsonParser::new(), r#"{"temp":21.5,"hum":40}"#).unwrap();
+        assert_eq!(
+            points,
+            vec![("hum".to_string(), 40.0), ("temp".to_string(), 21.5)]
+        );
+    }
+
+    #[test]
+    fn test_nested_paths_and_arrays() {
+        let points = parse(
+            &JsonParser::new(),
+            r#"{"imu":{"accel":{"x":0.1,"y":-0.2}},"samples":[1,2],"name":"a","ok":true}"#,
+        )
+        .unwrap();
+        let labels: Vec<_> = points.iter().map(|(l, _)| l.as_str()).collect();
+        assert_eq!(
+            labels,
+            vec!["imu.accel.x", "imu.accel.y", "samples.0", "samples.1"]
+        );
+    }
+
+    #[test]
+    fn test_include_exclude() {
+        let line = r#"{"imu":{"accel":{"x":1,"y":2},"gyro":{"x":3}},"temp":4,"temperature":5}"#;
+
+        let parser = JsonParser::new().with_include(["imu.accel", "temp"]);
+        let labels: Vec<_> = parse(&parser, line)
+            .unwrap()
+            .into_iter()
+            .map(|(l, _)| l)
+            .collect();
+        // "temp" does not select "temperature"
+        assert_eq!(labels, vec!["imu.accel.x", "imu.accel.y", "temp"]);
+
+        let parser = JsonParser::new().with_exclude(["imu.accel.y", "temperature"]);
+        let labels: Vec<_> = parse(&parser, line)
+            .unwrap()
+            .into_iter()
+            .map(|(l, _)| l)
+            .collect();
+        assert_eq!(labels, vec!["imu.accel.x", "imu.gyro.x", "temp"]);
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        assert!(parse(&JsonParser::new(), "temp=21.5").is_err());
+        assert!(parse(&JsonParser::new(), "[1, 2]").is_err());
+    }
+}
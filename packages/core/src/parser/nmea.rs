@@ -0,0 +1,300 @@
+use super::{Parse, ParserConfig};
+use crate::data::{Message, Point, Timestamp};
+use crate::framing::Checksum;
+use crate::{Error, Result};
+
+/// Decodes NMEA 0183 sentences (GGA, RMC, VTG, GSA, GSV from any talker)
+/// into labelled points.
+///
+/// | Sentence | Labels |
+/// |----------|--------|
+/// | GGA | `lat`, `lon`, `fix`, `sats`, `hdop`, `alt` |
+/// | RMC | `lat`, `lon`, `speed`, `course` (only when the fix is valid) |
+/// | VTG | `course`, `speed`, `speed_kmh` |
+/// | GSA | `fix_type`, `sats_used`, `pdop`, `hdop`, `vdop` |
+/// | GSV | `sats_in_view`, `snr.<prn>` |
+///
+/// Positions are signed decimal degrees, `speed` is in knots and `alt` in
+/// metres. Other sentence types yield no points. Empty fields are skipped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NmeaParser {
+    require_checksum: bool,
+}
+
+impl Default for NmeaParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NmeaParser {
+    pub fn new() -> Self {
+        Self {
+            require_checksum: true,
+        }
+    }
+
+    /// Whether sentences without a `*HH` checksum are rejected (the
+    /// default). A checksum that is present is always verified; disable
+    /// this when a `ChecksumFramer` has already verified and stripped it.
+    pub fn with_checksum_required(mut self, required: bool) -> Self {
+        self.require_checksum = required;
+        self
+    }
+
+    pub fn checksum_required(&self) -> bool {
+        self.require_checksum
+    }
+
+    fn sentence<'a>(&self, text: &'a str) -> Result<&'a str> {
+        let text = text.trim();
+        if !text.starts_with('$') {
+            return Err(Error::ParseError("Not an NMEA sentence".to_string()));
+        }
+        match text.rfind('*') {
+            Some(star) => {
+                Checksum::NmeaXor.verify(text.as_bytes())?;
+                Ok(&text[1..star])
+            }
+            None if self.require_checksum => {
+                Err(Error::ParseError("Missing NMEA checksum".to_string()))
+            }
+            None => Ok(&text[1..]),
+        }
+    }
+}
+
+impl Parse for NmeaParser {
+    fn parse_message(&self, message: &Message) -> Result<Vec<Point>> {
+        let sentence = self.sentence(message.text())?;
+        let fields: Vec<&str> = sentence.split(',').collect();
+        let address = fields[0];
+        // Addresses are ASCII, so slicing off the sentence type below is safe
+        if address.len() < 3 || !address.is_ascii() {
+            return Err(Error::ParseError(format!(
+                "Invalid NMEA address '{}'",
+                address
+            )));
+        }
+
+        let mut points = Points {
+            timestamp: message.timestamp(),
+            fields: &fields,
+            points: Vec::new(),
+        };
+        match &address[address.len() - 3..] {
+            "GGA" => {
+                points.position(2)?;
+                points.number("fix", 6)?;
+                points.number("sats", 7)?;
+                points.number("hdop", 8)?;
+                points.number("alt", 9)?;
+            }
+            "RMC" => {
+                if points.field(2) == "A" {
+                    points.position(3)?;
+                    points.number("speed", 7)?;
+                    points.number("course", 8)?;
+                }
+            }
+            "VTG" => {
+                points.number("course", 1)?;
+                points.number("speed", 5)?;
+                points.number("speed_kmh", 7)?;
+            }
+            "GSA" => {
+                points.number("fix_type", 2)?;
+                let used = (3..15).filter(|&i| !points.field(i).is_empty()).count();
+                points.push("sats_used", used as f64);
+                points.number("pdop", 15)?;
+                points.number("hdop", 16)?;
+                points.number("vdop", 17)?;
+            }
+            "GSV" => {
+                points.number("sats_in_view", 3)?;
+                for block in (4..fields.len()).step_by(4) {
+                    let prn = points.field(block);
+                    if !prn.is_empty() {
+                        points.number(&format!("snr.{}", prn), block + 3)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(points.points)
+    }
+
+    fn description(&self) -> String {
+        "NMEA 0183".to_string()
+    }
+
+    fn config(&self) -> ParserConfig {
+        ParserConfig::Nmea {
+            require_checksum: self.require_checksum,
+        }
+    }
+}
+
+/// Collects points from the fields of one sentence
+struct Points<'a> {
+    timestamp: Timestamp,
+    fields: &'a [&'a str],
+    points: Vec<Point>,
+}
+
+impl Points<'_> {
+    /// Field `index`, or `""` past the end
+    fn field(&self, index: usize) -> &str {
+        self.fields.get(index).copied().unwrap_or("")
+    }
+
+    fn push(&mut self, label: &str, value: f64) {
+        self.points
+            .push(Point::new(self.timestamp, value).with_label(label));
+    }
+
+    fn parse(&self, index: usize) -> Result<Option<f64>> {
+        let field = self.field(index);
+        if field.is_empty() {
+            return Ok(None);
+        }
+        field.parse().map(Some).map_err(|_| {
+            Error::ParseError(format!(
+                "Invalid number '{}' in NMEA field {}",
+                field, index
+            ))
+        })
+    }
+
+    fn number(&mut self, label: &str, index: usize) -> Result<()> {
+        if let Some(value) = self.parse(index)? {
+            self.push(label, value);
+        }
+        Ok(())
+    }
+
+    /// `lat`/`lon` from the four fields `ddmm.mm,N,dddmm.mm,E` at `index`
+    fn position(&mut self, index: usize) -> Result<()> {
+        for (label, offset, negative) in [("lat", 0, "S"), ("lon", 2, "W")] {
+            if let Some(raw) = self.parse(index + offset)? {
+                let degrees = (raw / 100.0).trunc();
+                let mut value = degrees + (raw - degrees * 100.0) / 60.0;
+                if self.field(index + offset + 1) == negative {
+                    value = -value;
+                }
+                self.push(label, value);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Direction;
+
+    fn parse(parser: &NmeaParser, sentence: &str) -> Result<Vec<(String, f64)>> {
+        let message = Message::new(Timestamp(9), Direction::In, sentence);
+        Ok(parser
+            .parse_message(&message)?
+            .into_iter()
+            .map(|p| (p.label().unwrap().to_string(), p.value()))
+            .collect())
+    }
+
+    fn value(points: &[(String, f64)], label: &str) -> f64 {
+        points.iter().find(|(l, _)| l == label).unwrap().1
+    }
+
+    fn approx(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-6
+    }
+
+    #[test]
+    fn test_gga() {
+        let points = parse(
+            &NmeaParser::new(),
+            "$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47\r\n",
+        )
+        .unwrap();
+        assert!(approx(value(&points, "lat"), 48.1173));
+        assert!(approx(value(&points, "lon"), 11.516_666_666));
+        assert_eq!(value(&points, "fix"), 1.0);
+        assert_eq!(value(&points, "sats"), 8.0);
+        assert_eq!(value(&points, "hdop"), 0.9);
+        assert_eq!(value(&points, "alt"), 545.4);
+    }
+
+    #[test]
+    fn test_rmc() {
+        let sentence = Checksum::NmeaXor
+            .append(b"$GPRMC,123519,A,4807.038,S,01131.000,W,022.4,084.4,230394,003.1,W");
+        let points = parse(&NmeaParser::new(), std::str::from_utf8(&sentence).unwrap()).unwrap();
+        assert!(approx(value(&points, "lat"), -48.1173));
+        assert!(approx(value(&points, "lon"), -11.516_666_666));
+        assert_eq!(value(&points, "speed"), 22.4);
+        assert_eq!(value(&points, "course"), 84.4);
+
+        // No fix: nothing to plot
+        let sentence = Checksum::NmeaXor.append(b"$GNRMC,123519,V,,,,,,,230394,,");
+        let points = parse(&NmeaParser::new(), std::str::from_utf8(&sentence).unwrap()).unwrap();
+        assert!(points.is_empty());
+    }
+
+    #[test]
+    fn test_vtg_gsa_gsv() {
+        let parser = NmeaParser::new();
+        let points = parse(&parser, "$GPVTG,054.7,T,034.4,M,005.5,N,010.2,K*48").unwrap();
+        assert_eq!(value(&points, "course"), 54.7);
+        assert_eq!(value(&points, "speed"), 5.5);
+        assert_eq!(value(&points, "speed_kmh"), 10.2);
+
+        let points = parse(&parser, "$GPGSA,A,3,04,05,,09,12,,,24,,,,,2.5,1.3,2.1*39").unwrap();
+        assert_eq!(value(&points, "fix_type"), 3.0);
+        assert_eq!(value(&points, "sats_used"), 5.0);
+        assert_eq!(value(&points, "pdop"), 2.5);
+        assert_eq!(value(&points, "hdop"), 1.3);
+        assert_eq!(value(&points, "vdop"), 2.1);
+
+        let points = parse(
+            &parser,
+            "$GPGSV,2,1,08,01,40,083,46,02,17,308,41,12,07,344,39,14,22,228,45*75",
+        )
+        .unwrap();
+        assert_eq!(value(&points, "sats_in_view"), 8.0);
+        assert_eq!(value(&points, "snr.01"), 46.0);
+        assert_eq!(value(&points, "snr.14"), 45.0);
+    }
+
+    #[test]
+    fn test_checksum() {
+        let parser = NmeaParser::new();
+        assert!(parse(&parser, "$GPVTG,054.7,T,034.4,M,005.5,N,010.2,K*49").is_err());
+        assert!(parse(&parser, "$GPVTG,054.7,T,034.4,M,005.5,N,010.2,K").is_err());
+
+        let parser = NmeaParser::new().with_checksum_required(false);
+        let points = parse(&parser, "$GPVTG,054.7,T,034.4,M,005.5,N,010.2,K").unwrap();
+        assert_eq!(points.len(), 3);
+    }
+
+    #[test]
+    fn test_other_input() {
+        let parser = NmeaParser::new();
+        assert!(parse(&parser, "temp=21.5").is_err());
+        let sentence = Checksum::NmeaXor.append(b"$GPZDA,201530.00,04,07,2002,00,00");
+        assert!(parse(&parser, std::str::from_utf8(&sentence).unwrap())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_non_ascii_address() {
+        let parser = NmeaParser::new();
+        for text in ["$GPGGÄ,1,2", "$éé,1", "$GP😀,1"] {
+            assert!(matches!(parse(&parser, text), Err(Error::ParseError(_))));
+        }
+        let sentence = Checksum::NmeaXor.append("$GPGGÄ,1".as_bytes());
+        assert!(parse(&parser, std::str::from_utf8(&sentence).unwrap()).is_err());
+    }
+}
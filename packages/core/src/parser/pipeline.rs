@@ -0,0 +1,129 @@
+use std::fmt;
+
+use super::{Parse, ParserConfig};
+use crate::data::{Message, Point};
+use crate::Result;
+
+/// Applies several parsers in sequence to each message and concatenates
+/// their points.
+///
+/// A parser that fails on a message (e.g. a regex that does not match a JSON
+/// line) contributes nothing; the message only fails if every parser does.
+#[derive(Default)]
+pub struct Pipeline {
+    parsers: Vec<Box<dyn Parse>>,
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, parser: Box<dyn Parse>) -> Self {
+        self.push(parser);
+        self
+    }
+
+    pub fn push(&mut self, parser: Box<dyn Parse>) {
+        self.parsers.push(parser);
+    }
+
+    /// Remove and return the parser at `index`
+    ///
+    /// # Panics
+    /// If `index` is out of bounds
+    pub fn remove(&mut self, index: usize) -> Box<dyn Parse> {
+        self.parsers.remove(index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Parse> {
+        self.parsers.iter().map(|parser| parser.as_ref())
+    }
+
+    pub fn len(&self) -> usize {
+        self.parsers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parsers.is_empty()
+    }
+}
+
+impl Parse for Pipeline {
+    fn parse_message(&self, message: &Message) -> Result<Vec<Point>> {
+        let mut points = Vec::new();
+        let mut first_error = None;
+        let mut any_ok = self.parsers.is_empty();
+        for parser in &self.parsers {
+            match parser.parse_message(message) {
+                Ok(parsed) => {
+                    any_ok = true;
+                    points.extend(parsed);
+                }
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        match first_error {
+            Some(e) if !any_ok => Err(e),
+            _ => Ok(points),
+        }
+    }
+
+    fn description(&self) -> String {
+        let descriptions: Vec<_> = self.iter().map(|parser| parser.description()).collect();
+        format!("Pipeline [{}]", descriptions.join(", "))
+    }
+
+    fn config(&self) -> ParserConfig {
+        ParserConfig::Pipeline {
+            parsers: self.iter().map(|parser| parser.config()).collect(),
+        }
+    }
+}
+
+impl fmt::Debug for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.iter().map(|parser| parser.description()))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Direction, Timestamp};
+    use crate::parser::{JsonParser, Parser};
+
+    fn message(text: &str) -> Message {
+        Message::new(Timestamp(0), Direction::In, text)
+    }
+
+    #[test]
+    fn test_applies_every_parser() {
+        let pipeline = Pipeline::new()
+            .with(Box::new(Parser::new(r"t=(\d+)", "t", "$1").unwrap()))
+            .with(Box::new(JsonParser::new()));
+
+        let points = pipeline.parse_message(&message(r#"{"h":2}"#)).unwrap();
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].label(), Some("h"));
+
+        let points = pipeline.parse_message(&message("t=1")).unwrap();
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].label(), Some("t"));
+
+        assert!(pipeline.parse_message(&message("nothing")).is_err());
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut pipeline = Pipeline::new().with(Box::new(JsonParser::new()));
+        assert_eq!(pipeline.len(), 1);
+        pipeline.remove(0);
+        assert!(pipeline.is_empty());
+        assert!(pipeline.parse_message(&message("x")).unwrap().is_empty());
+    }
+}
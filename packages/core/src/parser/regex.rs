@@ -1,30 +1,73 @@
 use crate::Result;
-use regex::Regex;
+use regex::{Captures, Regex};
+use std::collections::HashMap;
 
 /// Result of extraction from a string
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ExtractionResult {
     /// Captured groups from the regex match
     pub captures: Vec<String>,
+    /// Named groups (`(?P<name>...)`) from the regex match
+    pub named: HashMap<String, String>,
 }
 
 /// Extract captures from input string using a regex pattern
 pub fn extract(pattern: &str, input: &str) -> Result<ExtractionResult> {
-    let regex = Regex::new(pattern)
-        .map_err(|e| crate::Error::ParseError(format!("Invalid regex pattern: {}", e)))?;
+    extract_with(&compile(pattern)?, input)
+}
+
+/// Extract captures for every non-overlapping match in the input string
+pub fn extract_all(pattern: &str, input: &str) -> Result<Vec<ExtractionResult>> {
+    extract_all_with(&compile(pattern)?, input)
+}
 
+/// Compile a pattern once for use with `extract_with` / `extract_all_with`
+pub fn compile(pattern: &str) -> Result<Regex> {
+    Regex::new(pattern)
+        .map_err(|e| crate::Error::ConfigError(format!("Invalid regex pattern: {}", e)))
+}
+
+/// Extract captures from input string using a compiled regex
+pub fn extract_with(regex: &Regex, input: &str) -> Result<ExtractionResult> {
     let captures = regex
         .captures(input)
         .ok_or_else(|| crate::Error::ParseError("No match found".to_string()))?;
 
+    Ok(to_extraction(regex, &captures))
+}
+
+/// Extract captures for every match in the input string using a compiled regex
+pub fn extract_all_with(regex: &Regex, input: &str) -> Result<Vec<ExtractionResult>> {
+    let results = regex
+        .captures_iter(input)
+        .map(|captures| to_extraction(regex, &captures))
+        .collect::<Vec<_>>();
+    if results.is_empty() {
+        return Err(crate::Error::ParseError("No match found".to_string()));
+    }
+    Ok(results)
+}
+
+fn to_extraction(regex: &Regex, captures: &Captures) -> ExtractionResult {
     let captures_vec = captures
         .iter()
         .map(|m| m.map_or_else(String::new, |m| m.as_str().to_string()))
         .collect::<Vec<String>>();
 
-    Ok(ExtractionResult {
+    // Unmatched optional groups resolve to "" like positional captures
+    let named = regex
+        .capture_names()
+        .flatten()
+        .map(|name| {
+            let value = captures.name(name).map_or("", |m| m.as_str());
+            (name.to_string(), value.to_string())
+        })
+        .collect();
+
+    ExtractionResult {
         captures: captures_vec,
-    })
+        named,
+    }
 }
 
 #[cfg(test)]
@@ -44,6 +87,33 @@ mod tests {
         assert_eq!(result.captures[2], "25.5");
     }
 
+    #[test]
+    fn test_named_captures() {
+        let result = extract(r"(?P<sensor>\w+)=(?P<val>[-\d.]+)(?P<unit>%)?", "temp=-3.5").unwrap();
+        assert_eq!(result.named["sensor"], "temp");
+        assert_eq!(result.named["val"], "-3.5");
+        assert_eq!(result.named["unit"], "");
+        // Named groups are still numbered
+        assert_eq!(result.captures[1], "temp");
+    }
+
+    #[test]
+    fn test_extract_all() {
+        let results = extract_all(r"(\w+)=([-\d.]+)", "t=21.3 h=40.1 p=1013.2").unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[1].captures[1], "h");
+        assert_eq!(results[2].captures[2], "1013.2");
+        assert!(extract_all(r"(\w+)=(\d+)", "nothing here").is_err());
+    }
+
+    #[test]
+    fn test_invalid_pattern_is_config_error() {
+        assert!(matches!(
+            compile(r"(unclosed"),
+            Err(crate::Error::ConfigError(_))
+        ));
+    }
+
     #[test]
     fn test_no_match() {
         let result = extract(r"Temperature: (\d+\.\d+)", "Humidity: 50%");
@@ -1,7 +1,13 @@
+pub mod mock;
+pub mod rfc2217;
+pub mod watch;
+
 use crate::data::Message;
-use crate::error::Result;
+use crate::error::{Error, Result};
 
 use std::fmt::Debug;
+use std::ops::ControlFlow;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
@@ -23,6 +29,12 @@ pub enum PortType {
         product_id: Option<u16>,
         bluetooth_service_class_id: Option<String>,
     },
+    /// Serial line behind a TCP terminal server; `PortInfo::port` is its
+    /// `host:port` address. With `rfc2217` the line settings are pushed to
+    /// the server, otherwise the stream is raw serial data.
+    Network {
+        rfc2217: bool,
+    },
     /// Unknown or other port type. The contained String may be a debug
     /// representation from the platform crate.
     Other(String),
@@ -31,21 +43,88 @@ pub enum PortType {
 /// Static information about a physical port returned by `list_ports()`.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct PortInfo {
-    /// Port name (e.g., "COM1", "/dev/ttyUSB0")
+    /// Port name (e.g., "COM1", "/dev/ttyUSB0", "10.0.0.5:4001")
     pub port: String,
     /// Port type (USB/Bluetooth/etc)
     pub port_type: PortType,
     /// Optional human-friendly description or manufacturer string
     pub description: Option<String>,
+    /// Tells apart ports whose name does not, e.g. Web Serial ports, which
+    /// all share one name. Part of equality like the other fields.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
 }
 
 impl PortInfo {
-    /// Create a new PortInfo and generate a fresh id
+    /// Create a new PortInfo without an id
     pub fn new(port: String, port_type: PortType, description: Option<String>) -> Self {
         Self {
             port,
             port_type,
             description,
+            id: None,
+        }
+    }
+
+    pub fn with_id(mut self, id: u64) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    pub fn is_usb(&self) -> bool {
+        matches!(self.port_type, PortType::Usb { .. })
+    }
+
+    /// Whether `other` is the same physical device, possibly enumerated
+    /// under another path after it was unplugged. USB devices match by
+    /// serial number when they report one, otherwise by VID/PID and path;
+    /// other ports match by path and never match a USB device.
+    pub fn same_device(&self, other: &PortInfo) -> bool {
+        match (&self.port_type, &other.port_type) {
+            (
+                PortType::Usb {
+                    vendor_id,
+                    product_id,
+                    serial_number,
+                    ..
+                },
+                PortType::Usb {
+                    vendor_id: other_vendor_id,
+                    product_id: other_product_id,
+                    serial_number: other_serial_number,
+                    ..
+                },
+            ) => {
+                vendor_id == other_vendor_id
+                    && product_id == other_product_id
+                    && match serial_number {
+                        Some(_) => serial_number == other_serial_number,
+                        None => self.port == other.port,
+                    }
+            }
+            (PortType::Usb { .. }, _) | (_, PortType::Usb { .. }) => false,
+            _ => self.port == other.port,
+        }
+    }
+
+    /// Whether both are USB devices with the same known VID/PID. Identical
+    /// boards without serial numbers cannot be told apart, so this alone
+    /// does not make them the same device.
+    pub fn same_model(&self, other: &PortInfo) -> bool {
+        match (&self.port_type, &other.port_type) {
+            (
+                PortType::Usb {
+                    vendor_id: Some(vendor_id),
+                    product_id,
+                    ..
+                },
+                PortType::Usb {
+                    vendor_id: other_vendor_id,
+                    product_id: other_product_id,
+                    ..
+                },
+            ) => Some(vendor_id) == other_vendor_id.as_ref() && product_id == other_product_id,
+            _ => false,
         }
     }
 }
@@ -56,22 +135,108 @@ impl Default for PortInfo {
     }
 }
 
+/// Parity checking mode
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum Parity {
+    #[default]
+    None,
+    Odd,
+    Even,
+}
+
+/// Flow control mode
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum FlowControl {
+    #[default]
+    None,
+    /// XON/XOFF in-band flow control
+    Software,
+    /// RTS/CTS out-of-band flow control
+    Hardware,
+}
+
 /// Configuration used to open a port (baud rate, data bits, stop bits, ...)
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct PortConfig {
     pub baud_rate: u32,
     pub data_bits: u8,
     pub stop_bits: u8,
+    pub parity: Parity,
+    pub flow_control: FlowControl,
+    /// How long a single read may block before giving up
+    pub read_timeout: Duration,
+    /// Size of the read buffer in bytes
+    pub buffer_size: usize,
 }
 
 impl PortConfig {
+    /// Largest buffer accepted by `validate()` (Web Serial limit is 16 MiB)
+    pub const MAX_BUFFER_SIZE: usize = 16 * 1024 * 1024;
+
     pub fn new(baud_rate: u32, data_bits: u8, stop_bits: u8) -> Self {
         Self {
             baud_rate,
             data_bits,
             stop_bits,
+            parity: Parity::None,
+            flow_control: FlowControl::None,
+            read_timeout: Duration::from_millis(100),
+            buffer_size: 1024,
         }
     }
+
+    pub fn with_parity(mut self, parity: Parity) -> Self {
+        self.parity = parity;
+        self
+    }
+
+    pub fn with_flow_control(mut self, flow_control: FlowControl) -> Self {
+        self.flow_control = flow_control;
+        self
+    }
+
+    pub fn with_read_timeout(mut self, read_timeout: Duration) -> Self {
+        self.read_timeout = read_timeout;
+        self
+    }
+
+    pub fn with_buffer_size(mut self, buffer_size: usize) -> Self {
+        self.buffer_size = buffer_size;
+        self
+    }
+
+    /// Check that the configuration can be applied to a real port.
+    /// Platform crates call this before opening.
+    pub fn validate(&self) -> Result<()> {
+        if self.baud_rate == 0 {
+            return Err(Error::ConfigError("Baud rate must be non-zero".to_string()));
+        }
+        if !(5..=8).contains(&self.data_bits) {
+            return Err(Error::ConfigError(format!(
+                "Invalid data bits: {} (expected 5-8)",
+                self.data_bits
+            )));
+        }
+        if !matches!(self.stop_bits, 1 | 2) {
+            return Err(Error::ConfigError(format!(
+                "Invalid stop bits: {} (expected 1 or 2)",
+                self.stop_bits
+            )));
+        }
+        if self.buffer_size == 0 || self.buffer_size > Self::MAX_BUFFER_SIZE {
+            return Err(Error::ConfigError(format!(
+                "Invalid buffer size: {} (expected 1-{})",
+                self.buffer_size,
+                Self::MAX_BUFFER_SIZE
+            )));
+        }
+        if self.read_timeout.is_zero() {
+            return Err(Error::ConfigError(
+                "Read timeout must be non-zero".to_string(),
+            ));
+        }
+        Ok(())
+    }
 }
 
 impl Default for PortConfig {
@@ -91,6 +256,19 @@ pub trait SerialPortConfig {
 /// Trait for platform-agnostic serial port communication
 #[async_trait(?Send)]
 pub trait SerialPort: SerialPortConfig + Sized + Debug + PartialEq + Clone {
+    /// Whether `request_port` accepts `PortType::Network` infos
+    const NETWORK_PORTS: bool = false;
+
+    /// Enumerate ports currently available on this platform.
+    /// Ports returned here are not opened.
+    async fn list_ports() -> Result<Vec<PortInfo>>;
+
+    /// Report ports as they are added and removed, until `on_event` breaks.
+    /// Ports present when called are not reported.
+    async fn watch_ports(
+        on_event: &mut dyn FnMut(watch::PortEvent) -> ControlFlow<()>,
+    ) -> Result<()>;
+
     async fn request_port(info: PortInfo, config: PortConfig) -> Result<Self>;
 
     /// Open the serial port
@@ -100,7 +278,8 @@ pub trait SerialPort: SerialPortConfig + Sized + Debug + PartialEq + Clone {
     async fn close(&mut self) -> Result<()>;
 
     /// Read data from the serial port
-    /// Returns a Message
+    /// Returns a Message, or `Error::Timeout` if nothing arrived within
+    /// `PortConfig::read_timeout` (where the platform supports timeouts)
     async fn read(&mut self) -> Result<Message>;
 
     /// Write data to the serial port
@@ -118,3 +297,91 @@ pub trait SerialPort: SerialPortConfig + Sized + Debug + PartialEq + Clone {
     /// Check if the port is open
     fn is_open(&self) -> bool;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config_is_valid() {
+        assert!(PortConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_config_builders() {
+        let config = PortConfig::new(19200, 8, 1)
+            .with_parity(Parity::Even)
+            .with_flow_control(FlowControl::Hardware)
+            .with_read_timeout(Duration::from_millis(500))
+            .with_buffer_size(4096);
+        assert_eq!(config.parity, Parity::Even);
+        assert_eq!(config.flow_control, FlowControl::Hardware);
+        assert_eq!(config.read_timeout, Duration::from_millis(500));
+        assert_eq!(config.buffer_size, 4096);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        assert!(PortConfig::new(0, 8, 1).validate().is_err());
+        assert!(PortConfig::new(9600, 9, 1).validate().is_err());
+        assert!(PortConfig::new(9600, 8, 3).validate().is_err());
+        assert!(PortConfig::default()
+            .with_buffer_size(0)
+            .validate()
+            .is_err());
+        assert!(PortConfig::default()
+            .with_read_timeout(Duration::ZERO)
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn test_same_device() {
+        let usb = |port: &str, serial_number: Option<&str>| {
+            PortInfo::new(
+                port.to_string(),
+                PortType::Usb {
+                    vendor_id: Some(0x2E8A),
+                    product_id: Some(0x000A),
+                    product_name: None,
+                    manufacturer: None,
+                    serial_number: serial_number.map(str::to_string),
+                },
+                None,
+            )
+        };
+        let board = usb("/dev/ttyACM0", Some("E660"));
+        assert!(board.same_device(&usb("/dev/ttyACM1", Some("E660"))));
+        assert!(!board.same_device(&usb("/dev/ttyACM0", Some("F770"))));
+        // Without a serial number, only the same VID/PID on the same path
+        assert!(usb("/dev/ttyACM0", None).same_device(&usb("/dev/ttyACM0", None)));
+        assert!(!usb("/dev/ttyACM0", None).same_device(&usb("/dev/ttyACM1", None)));
+        assert!(usb("/dev/ttyACM0", None).same_model(&usb("/dev/ttyACM1", None)));
+
+        let uart = PortInfo::new("/dev/ttyS0".to_string(), PortType::Pci, None);
+        assert!(uart.same_device(&uart.clone()));
+        assert!(!uart.same_device(&usb("/dev/ttyS0", None)));
+    }
+
+    #[test]
+    fn test_port_id() {
+        let web = PortInfo::new(
+            "WebSerial Device".to_string(),
+            PortType::WebSerial {
+                vendor_id: None,
+                product_id: None,
+                bluetooth_service_class_id: None,
+            },
+            None,
+        );
+        assert_ne!(web.clone().with_id(1), web.clone().with_id(2));
+        assert_eq!(web.clone().with_id(1), web.clone().with_id(1));
+
+        // Ports without an id serialize as before
+        let uart = PortInfo::new("/dev/ttyS0".to_string(), PortType::Pci, None);
+        let json = serde_json::to_string(&uart).unwrap();
+        assert!(!json.contains("id"));
+        assert_eq!(serde_json::from_str::<PortInfo>(&json).unwrap(), uart);
+    }
+}
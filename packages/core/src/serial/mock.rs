@@ -0,0 +1,490 @@
+//! In-memory serial ports for tests and demos.
+//!
+//! `MockSerialPort` needs no hardware: it can echo writes back (loopback),
+//! replay a script of timed chunks, or answer each write through a
+//! responder closure, and it records everything written to it. Ports can be
+//! plugged in and unplugged to simulate hot-plugging; `list_ports` reports
+//! the ports plugged in on the current thread. Timing uses `tokio::time`, so
+//! it must run inside a Tokio runtime; tests can pause the clock to make
+//! timing deterministic.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt;
+use std::future::Future;
+use std::ops::ControlFlow;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+use super::watch::{self, PortEvent};
+use super::{PortConfig, PortInfo, PortType, SerialPort, SerialPortConfig};
+use crate::data::{Direction, Message, Timestamp};
+use crate::error::{Error, Result};
+use crate::system::TimeSource;
+
+/// `TimeSource` on the Tokio clock, counting from the first call. It
+/// follows `tokio::time::pause` and `advance`, matching `MockSerialPort`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockTimeSource;
+
+impl TimeSource for MockTimeSource {
+    fn now_millis() -> Timestamp {
+        static START: OnceLock<Instant> = OnceLock::new();
+        let start = *START.get_or_init(Instant::now);
+        Timestamp(Instant::now().saturating_duration_since(start).as_millis() as u64)
+    }
+
+    fn sleep(duration: Duration) -> Pin<Box<dyn Future<Output = ()>>> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// One step of a script: after `delay`, the port receives `data`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptChunk {
+    pub delay: Duration,
+    pub data: Vec<u8>,
+}
+
+impl ScriptChunk {
+    pub fn new(delay: Duration, data: impl Into<Vec<u8>>) -> Self {
+        Self {
+            delay,
+            data: data.into(),
+        }
+    }
+}
+
+type Responder = Box<dyn FnMut(&[u8]) -> Option<Vec<u8>>>;
+
+enum Mode {
+    /// Written bytes are read back
+    Loopback,
+    /// Chunks are delivered in order, each `delay` after the previous one
+    /// was read; reads fail once the script is exhausted
+    Scripted(VecDeque<ScriptChunk>),
+    /// Each write may produce a reply
+    Responder(Responder),
+}
+
+struct State {
+    mode: Mode,
+    /// Bytes waiting to be read
+    input: VecDeque<u8>,
+    writes: Vec<Message>,
+    is_open: bool,
+    unplugged: bool,
+}
+
+thread_local! {
+    /// Ports reported by `list_ports`
+    static PLUGGED: RefCell<Vec<PortInfo>> = const { RefCell::new(Vec::new()) };
+}
+
+/// In-memory `SerialPort`. Clones share the same port, as with the
+/// platform implementations.
+#[derive(Clone)]
+pub struct MockSerialPort {
+    info: PortInfo,
+    config: PortConfig,
+    state: Rc<RefCell<State>>,
+    /// Wakes a pending read when input arrives or the port closes
+    wake: Rc<Notify>,
+}
+
+impl MockSerialPort {
+    fn with_mode(mode: Mode) -> Self {
+        Self {
+            info: PortInfo::new(
+                "mock".to_string(),
+                PortType::Other("mock".to_string()),
+                Some("In-memory mock port".to_string()),
+            ),
+            config: PortConfig::default(),
+            state: Rc::new(RefCell::new(State {
+                mode,
+                input: VecDeque::new(),
+                writes: Vec::new(),
+                is_open: false,
+                unplugged: false,
+            })),
+            wake: Rc::new(Notify::new()),
+        }
+    }
+
+    /// A port that reads back whatever is written to it
+    pub fn loopback() -> Self {
+        Self::with_mode(Mode::Loopback)
+    }
+
+    /// A port that replays `chunks`, then fails further reads with
+    /// `Error::ReadError`
+    pub fn scripted(chunks: impl IntoIterator<Item = ScriptChunk>) -> Self {
+        Self::with_mode(Mode::Scripted(chunks.into_iter().collect()))
+    }
+
+    /// A port that passes each write to `responder` and makes its reply
+    /// available to read, e.g. to simulate a Modbus slave
+    pub fn responder(responder: impl FnMut(&[u8]) -> Option<Vec<u8>> + 'static) -> Self {
+        Self::with_mode(Mode::Responder(Box::new(responder)))
+    }
+
+    pub fn with_info(mut self, info: PortInfo) -> Self {
+        self.info = info;
+        self
+    }
+
+    /// Attach the device: it is listed by `list_ports` and can be opened
+    pub fn plug_in(&self) {
+        self.state.borrow_mut().unplugged = false;
+        PLUGGED.with_borrow_mut(|plugged| {
+            if !plugged.contains(&self.info) {
+                plugged.push(self.info.clone());
+            }
+        });
+    }
+
+    /// Detach the device: it disappears from `list_ports`, pending and
+    /// later reads fail and it cannot be opened until plugged in again
+    pub fn unplug(&self) {
+        {
+            let mut state = self.state.borrow_mut();
+            state.unplugged = true;
+            state.is_open = false;
+        }
+        PLUGGED.with_borrow_mut(|plugged| plugged.retain(|info| info != &self.info));
+        self.wake.notify_waiters();
+    }
+
+    /// Make `data` available to read, as if the device had sent it
+    pub fn push_input(&self, data: &[u8]) {
+        self.state.borrow_mut().input.extend(data);
+        self.wake.notify_waiters();
+    }
+
+    /// Every message written so far
+    pub fn writes(&self) -> Vec<Message> {
+        self.state.borrow().writes.clone()
+    }
+
+    /// Take the pending input, up to the configured buffer size
+    fn take_input(&self) -> Option<Message> {
+        let mut state = self.state.borrow_mut();
+        if state.input.is_empty() {
+            return None;
+        }
+        let len = state.input.len().min(self.config.buffer_size);
+        let data: Vec<u8> = state.input.drain(..len).collect();
+        Some(Message::new(
+            MockTimeSource::now_millis(),
+            Direction::In,
+            data,
+        ))
+    }
+
+    fn check_open(&self) -> Result<()> {
+        let state = self.state.borrow();
+        if state.unplugged {
+            Err(Error::ReadError("Mock device was unplugged".to_string()))
+        } else if state.is_open {
+            Ok(())
+        } else {
+            Err(Error::ReadError("Mock port is not open".to_string()))
+        }
+    }
+
+    fn timeout(&self) -> Error {
+        Error::Timeout(format!("No data within {:?}", self.config.read_timeout))
+    }
+
+    async fn read_scripted(&mut self) -> Result<Message> {
+        let delay = {
+            let mut state = self.state.borrow_mut();
+            let Mode::Scripted(chunks) = &mut state.mode else {
+                unreachable!("only called in scripted mode");
+            };
+            let Some(chunk) = chunks.front_mut() else {
+                return Err(Error::ReadError("Mock script exhausted".to_string()));
+            };
+            // Waits longer than the read timeout time out like a real port
+            // would, leaving the rest of the delay for the next read
+            if chunk.delay > self.config.read_timeout {
+                chunk.delay -= self.config.read_timeout;
+                None
+            } else {
+                Some(chunk.delay)
+            }
+        };
+
+        let Some(delay) = delay else {
+            tokio::time::sleep(self.config.read_timeout).await;
+            return Err(self.timeout());
+        };
+        tokio::time::sleep(delay).await;
+        self.check_open()?;
+        let chunk = {
+            let mut state = self.state.borrow_mut();
+            let Mode::Scripted(chunks) = &mut state.mode else {
+                unreachable!("only called in scripted mode");
+            };
+            chunks.pop_front()
+        };
+        let data = chunk.map(|chunk| chunk.data).unwrap_or_default();
+        Ok(Message::new(
+            MockTimeSource::now_millis(),
+            Direction::In,
+            data,
+        ))
+    }
+}
+
+impl fmt::Debug for MockSerialPort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state.borrow();
+        let mode = match state.mode {
+            Mode::Loopback => "loopback",
+            Mode::Scripted(_) => "scripted",
+            Mode::Responder(_) => "responder",
+        };
+        f.debug_struct("MockSerialPort")
+            .field("info", &self.info)
+            .field("config", &self.config)
+            .field("mode", &mode)
+            .field("is_open", &state.is_open)
+            .finish()
+    }
+}
+
+impl PartialEq for MockSerialPort {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.state, &other.state)
+    }
+}
+
+impl SerialPortConfig for MockSerialPort {
+    fn with_port(mut self, port: String) -> Self {
+        self.info.port = port;
+        self
+    }
+
+    fn with_config(mut self, config: PortConfig) -> Self {
+        self.config = config;
+        self
+    }
+}
+
+#[async_trait(?Send)]
+impl SerialPort for MockSerialPort {
+    async fn list_ports() -> Result<Vec<PortInfo>> {
+        Ok(PLUGGED.with_borrow(|plugged| plugged.clone()))
+    }
+
+    /// Polls `list_ports` every 100 ms
+    async fn watch_ports(on_event: &mut dyn FnMut(PortEvent) -> ControlFlow<()>) -> Result<()> {
+        watch::poll::<Self, MockTimeSource>(Duration::from_millis(100), on_event).await
+    }
+
+    /// A loopback port named after `info`
+    async fn request_port(info: PortInfo, config: PortConfig) -> Result<Self> {
+        config.validate()?;
+        let mut port = Self::loopback().with_config(config);
+        if !info.port.is_empty() {
+            port.info = info;
+        }
+        Ok(port)
+    }
+
+    async fn open(&mut self) -> Result<()> {
+        self.config.validate()?;
+        let mut state = self.state.borrow_mut();
+        if state.unplugged {
+            return Err(Error::DeviceNotFound(self.info.port.clone()));
+        }
+        if state.is_open {
+            return Err(Error::OpenError("Mock port is already open".to_string()));
+        }
+        state.is_open = true;
+        Ok(())
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        self.state.borrow_mut().is_open = false;
+        self.wake.notify_waiters();
+        Ok(())
+    }
+
+    async fn read(&mut self) -> Result<Message> {
+        self.check_open()?;
+        if let Some(message) = self.take_input() {
+            return Ok(message);
+        }
+        if matches!(self.state.borrow().mode, Mode::Scripted(_)) {
+            return self.read_scripted().await;
+        }
+
+        // Either input arrives, the port closes, or the read times out
+        let _ = tokio::time::timeout(self.config.read_timeout, self.wake.notified()).await;
+        self.check_open()?;
+        self.take_input().ok_or_else(|| self.timeout())
+    }
+
+    async fn write(&mut self, message: Message) -> Result<()> {
+        let reply = {
+            let mut state = self.state.borrow_mut();
+            if !state.is_open {
+                return Err(Error::WriteError("Mock port is not open".to_string()));
+            }
+            state.writes.push(message.clone());
+            match &mut state.mode {
+                Mode::Loopback => Some(message.bytes().to_vec()),
+                Mode::Scripted(_) => None,
+                Mode::Responder(responder) => responder(message.bytes()),
+            }
+        };
+        if let Some(reply) = reply {
+            self.push_input(&reply);
+        }
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn config(&self) -> &PortConfig {
+        &self.config
+    }
+
+    fn info(&self) -> &PortInfo {
+        &self.info
+    }
+
+    fn is_open(&self) -> bool {
+        self.state.borrow().is_open
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn out(data: &[u8]) -> Message {
+        Message::new(Timestamp(0), Direction::Out, data)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_loopback() {
+        let mut port = MockSerialPort::loopback();
+        assert!(port.read().await.is_err());
+        port.open().await.unwrap();
+
+        port.write(out(b"ping")).await.unwrap();
+        assert_eq!(port.read().await.unwrap().bytes(), b"ping");
+        assert_eq!(port.writes(), vec![out(b"ping")]);
+
+        // Nothing left: times out after the read timeout
+        let start = Instant::now();
+        assert!(matches!(port.read().await, Err(Error::Timeout(_))));
+        assert_eq!(start.elapsed(), port.config().read_timeout);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_pending_read_sees_later_input() {
+        let mut port = MockSerialPort::loopback();
+        port.open().await.unwrap();
+        let mut reader = port.clone();
+
+        let (read, ()) = tokio::join!(reader.read(), async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            port.push_input(b"late");
+        });
+        assert_eq!(read.unwrap().bytes(), b"late");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_scripted_timing() {
+        let mut port = MockSerialPort::scripted([
+            ScriptChunk::new(Duration::from_millis(20), "a"),
+            ScriptChunk::new(Duration::from_millis(250), "b"),
+        ])
+        .with_config(PortConfig::default().with_read_timeout(Duration::from_millis(100)));
+        port.open().await.unwrap();
+
+        let first = port.read().await.unwrap();
+        assert_eq!(first.bytes(), b"a");
+
+        // 250 ms exceeds the 100 ms read timeout twice before "b" arrives
+        assert!(matches!(port.read().await, Err(Error::Timeout(_))));
+        assert!(matches!(port.read().await, Err(Error::Timeout(_))));
+        let second = port.read().await.unwrap();
+        assert_eq!(second.bytes(), b"b");
+        assert_eq!(
+            second.timestamp().as_millis() - first.timestamp().as_millis(),
+            250
+        );
+
+        assert!(matches!(port.read().await, Err(Error::ReadError(_))));
+
+        // Writes are recorded but not echoed
+        port.write(out(b"cmd")).await.unwrap();
+        assert_eq!(port.writes().len(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_responder() {
+        let mut port = MockSerialPort::responder(|request| match request {
+            b"?" => Some(b"42\n".to_vec()),
+            _ => None,
+        });
+        port.open().await.unwrap();
+
+        port.write(out(b"x")).await.unwrap();
+        port.write(out(b"?")).await.unwrap();
+        assert_eq!(port.read().await.unwrap().bytes(), b"42\n");
+        assert_eq!(port.writes().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_open_close() {
+        let mut port = MockSerialPort::request_port(
+            PortInfo::new("demo".to_string(), PortType::Pci, None),
+            PortConfig::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(port.info().port, "demo");
+        port.open().await.unwrap();
+        assert!(port.clone().open().await.is_err());
+        port.close().await.unwrap();
+        assert!(!port.is_open());
+        assert!(port.write(out(b"x")).await.is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_plug_and_unplug() {
+        let mut port = MockSerialPort::loopback();
+        assert!(MockSerialPort::list_ports().await.unwrap().is_empty());
+        port.plug_in();
+        assert_eq!(
+            MockSerialPort::list_ports().await.unwrap(),
+            vec![port.info().clone()]
+        );
+        port.open().await.unwrap();
+
+        // A pending read fails as soon as the device goes away
+        let mut reader = port.clone();
+        let (read, ()) = tokio::join!(reader.read(), async { port.unplug() });
+        assert!(matches!(read, Err(Error::ReadError(_))));
+        assert!(MockSerialPort::list_ports().await.unwrap().is_empty());
+        assert!(matches!(port.open().await, Err(Error::DeviceNotFound(_))));
+
+        port.plug_in();
+        port.open().await.unwrap();
+    }
+}
@@ -0,0 +1,302 @@
+//! Telnet framing and RFC 2217 COM port control for network serial ports.
+//!
+//! Terminal servers such as ser2net or Moxa NPort expose a serial line over
+//! TCP. In raw mode the TCP stream is the serial data; with RFC 2217 the
+//! stream is Telnet, so 0xFF bytes are escaped and the client can push line
+//! settings to the server. This module only produces and consumes bytes;
+//! platform crates own the socket.
+
+use super::{FlowControl, Parity, PortConfig};
+
+/// Interpret As Command: starts every Telnet command
+pub const IAC: u8 = 255;
+pub const DONT: u8 = 254;
+pub const DO: u8 = 253;
+pub const WONT: u8 = 252;
+pub const WILL: u8 = 251;
+/// Subnegotiation begin
+pub const SB: u8 = 250;
+/// Subnegotiation end
+pub const SE: u8 = 240;
+
+/// Telnet option: 8-bit clean data
+pub const BINARY: u8 = 0;
+/// Telnet option: suppress go-ahead
+pub const SUPPRESS_GO_AHEAD: u8 = 3;
+/// Telnet option: RFC 2217 COM port control
+pub const COM_PORT_OPTION: u8 = 44;
+
+/// Client-to-server COM port commands. The server answers each with the
+/// same command plus `SERVER_OFFSET`.
+pub const SET_BAUDRATE: u8 = 1;
+pub const SET_DATASIZE: u8 = 2;
+pub const SET_PARITY: u8 = 3;
+pub const SET_STOPSIZE: u8 = 4;
+pub const SET_CONTROL: u8 = 5;
+pub const SERVER_OFFSET: u8 = 100;
+
+/// Double every IAC so data passes through Telnet unchanged
+pub fn escape(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    for &byte in data {
+        out.push(byte);
+        if byte == IAC {
+            out.push(IAC);
+        }
+    }
+    out
+}
+
+/// A COM port subnegotiation: `IAC SB COM-PORT-OPTION command value IAC SE`
+pub fn subnegotiation(command: u8, value: &[u8]) -> Vec<u8> {
+    let mut out = vec![IAC, SB, COM_PORT_OPTION, command];
+    out.extend(escape(value));
+    out.extend([IAC, SE]);
+    out
+}
+
+/// The options a client asks for right after connecting. Line settings
+/// follow with `settings` once the server agrees to COM port control.
+pub fn negotiate() -> Vec<u8> {
+    vec![
+        IAC,
+        WILL,
+        COM_PORT_OPTION,
+        IAC,
+        WILL,
+        BINARY,
+        IAC,
+        DO,
+        BINARY,
+        IAC,
+        WILL,
+        SUPPRESS_GO_AHEAD,
+        IAC,
+        DO,
+        SUPPRESS_GO_AHEAD,
+    ]
+}
+
+/// COM port subnegotiations pushing the line settings from `config`
+pub fn settings(config: &PortConfig) -> Vec<u8> {
+    let parity = match config.parity {
+        Parity::None => 1,
+        Parity::Odd => 2,
+        Parity::Even => 3,
+    };
+    let control = match config.flow_control {
+        FlowControl::None => 1,
+        FlowControl::Software => 2,
+        FlowControl::Hardware => 3,
+    };
+    let mut out = subnegotiation(SET_BAUDRATE, &config.baud_rate.to_be_bytes());
+    out.extend(subnegotiation(SET_DATASIZE, &[config.data_bits]));
+    out.extend(subnegotiation(SET_PARITY, &[parity]));
+    out.extend(subnegotiation(SET_STOPSIZE, &[config.stop_bits]));
+    out.extend(subnegotiation(SET_CONTROL, &[control]));
+    out
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum State {
+    #[default]
+    Data,
+    Iac,
+    /// After `IAC <verb>`, waiting for the option byte
+    Option(u8),
+    Subnegotiation,
+    SubnegotiationIac,
+}
+
+/// Splits a Telnet stream into serial data and commands, one chunk at a
+/// time. Commands may be split across chunks.
+#[derive(Debug, Clone, Default)]
+pub struct TelnetDecoder {
+    state: State,
+    subnegotiation: Vec<u8>,
+    /// Answers owed to the server, drained by `take_replies`
+    replies: Vec<u8>,
+    /// `(verb, option)` pairs already answered, so each is answered once
+    answered: Vec<(u8, u8)>,
+    com_port: Option<bool>,
+    /// COM port subnegotiations received, as `(command, value)`; `None`
+    /// unless asked for with `with_notifications`
+    notifications: Option<Vec<(u8, Vec<u8>)>>,
+}
+
+impl TelnetDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep COM port subnegotiations for `take_notifications`. Off by
+    /// default, so a client that ignores them does not accumulate them.
+    pub fn with_notifications(mut self) -> Self {
+        self.notifications = Some(Vec::new());
+        self
+    }
+
+    /// Feed received bytes; returns the serial data among them
+    pub fn decode(&mut self, input: &[u8]) -> Vec<u8> {
+        let mut data = Vec::with_capacity(input.len());
+        for &byte in input {
+            self.state = match (self.state, byte) {
+                (State::Data, IAC) => State::Iac,
+                (State::Data, _) => {
+                    data.push(byte);
+                    State::Data
+                }
+                (State::Iac, IAC) => {
+                    data.push(IAC);
+                    State::Data
+                }
+                (State::Iac, DO | DONT | WILL | WONT) => State::Option(byte),
+                (State::Iac, SB) => {
+                    self.subnegotiation.clear();
+                    State::Subnegotiation
+                }
+                // NOP, go-ahead and other two-byte commands carry nothing
+                (State::Iac, _) => State::Data,
+                (State::Option(verb), option) => {
+                    self.option(verb, option);
+                    State::Data
+                }
+                (State::Subnegotiation, IAC) => State::SubnegotiationIac,
+                (State::Subnegotiation, _) => {
+                    self.subnegotiation.push(byte);
+                    State::Subnegotiation
+                }
+                (State::SubnegotiationIac, IAC) => {
+                    self.subnegotiation.push(IAC);
+                    State::Subnegotiation
+                }
+                (State::SubnegotiationIac, SE) => {
+                    self.end_subnegotiation();
+                    State::Data
+                }
+                // Malformed; drop the subnegotiation
+                (State::SubnegotiationIac, _) => State::Data,
+            };
+        }
+        data
+    }
+
+    /// Bytes that must be sent back to the server
+    pub fn take_replies(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.replies)
+    }
+
+    /// `Some(true)` once the server agreed to COM port control,
+    /// `Some(false)` if it refused, `None` while undecided
+    pub fn com_port(&self) -> Option<bool> {
+        self.com_port
+    }
+
+    /// COM port subnegotiations received since the last call, as
+    /// `(command, value)`; always empty without `with_notifications`
+    pub fn take_notifications(&mut self) -> Vec<(u8, Vec<u8>)> {
+        self.notifications
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    fn option(&mut self, verb: u8, option: u8) {
+        let reply = match (verb, option) {
+            (DO, COM_PORT_OPTION) => {
+                self.com_port = Some(true);
+                None
+            }
+            (DONT, COM_PORT_OPTION) => {
+                self.com_port = Some(false);
+                None
+            }
+            // Requested by `negotiate`; agreement needs no answer
+            (DO | WILL, BINARY | SUPPRESS_GO_AHEAD) => None,
+            (DO, _) => Some(WONT),
+            (WILL, _) => Some(DONT),
+            // Refusals need no answer
+            _ => None,
+        };
+        if let Some(reply) = reply {
+            if !self.answered.contains(&(verb, option)) {
+                self.answered.push((verb, option));
+                self.replies.extend([IAC, reply, option]);
+            }
+        }
+    }
+
+    fn end_subnegotiation(&mut self) {
+        let Some(notifications) = self.notifications.as_mut() else {
+            return;
+        };
+        if let [COM_PORT_OPTION, command, value @ ..] = self.subnegotiation.as_slice() {
+            notifications.push((*command, value.to_vec()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape(&[1, IAC, 2]), vec![1, IAC, IAC, 2]);
+        assert_eq!(
+            subnegotiation(SET_BAUDRATE, &[0, 0, 0, IAC]),
+            vec![IAC, SB, 44, 1, 0, 0, 0, IAC, IAC, IAC, SE]
+        );
+    }
+
+    #[test]
+    fn test_negotiate_then_settings() {
+        assert!(negotiate().starts_with(&[IAC, WILL, COM_PORT_OPTION]));
+        assert!(!negotiate().contains(&SB));
+
+        let config = PortConfig::new(115200, 7, 2).with_parity(Parity::Even);
+        let bytes = settings(&config);
+        let find = |needle: &[u8]| bytes.windows(needle.len()).any(|w| w == needle);
+        assert!(find(&subnegotiation(SET_BAUDRATE, &[0, 1, 0xC2, 0])));
+        assert!(find(&subnegotiation(SET_DATASIZE, &[7])));
+        assert!(find(&subnegotiation(SET_PARITY, &[3])));
+        assert!(find(&subnegotiation(SET_STOPSIZE, &[2])));
+        assert!(find(&subnegotiation(SET_CONTROL, &[1])));
+    }
+
+    #[test]
+    fn test_decode_data_and_commands() {
+        let mut decoder = TelnetDecoder::new();
+        let data = decoder.decode(&[b'a', IAC, IAC, IAC, DO, COM_PORT_OPTION, b'b']);
+        assert_eq!(data, vec![b'a', IAC, b'b']);
+        assert_eq!(decoder.com_port(), Some(true));
+        assert!(decoder.take_replies().is_empty());
+
+        // Unknown options are refused, once each
+        decoder.decode(&[IAC, WILL, 1, IAC, DO, 24, IAC, WILL, 1]);
+        assert_eq!(decoder.take_replies(), vec![IAC, DONT, 1, IAC, WONT, 24]);
+
+        let mut refused = TelnetDecoder::new();
+        refused.decode(&[IAC, DONT, COM_PORT_OPTION]);
+        assert_eq!(refused.com_port(), Some(false));
+    }
+
+    #[test]
+    fn test_decode_split_subnegotiation() {
+        let mut decoder = TelnetDecoder::new().with_notifications();
+        let reply = subnegotiation(SET_BAUDRATE + SERVER_OFFSET, &[0, 0, 0x25, 0x80]);
+        let (head, tail) = reply.split_at(3);
+        assert_eq!(decoder.decode(head), Vec::<u8>::new());
+        assert_eq!(decoder.decode(&[tail, b"ok"].concat()), b"ok".to_vec());
+        assert_eq!(
+            decoder.take_notifications(),
+            vec![(101, vec![0, 0, 0x25, 0x80])]
+        );
+
+        // Not kept unless asked for
+        let mut decoder = TelnetDecoder::new();
+        assert_eq!(decoder.decode(&[reply.as_slice(), b"ok"].concat()), b"ok");
+        assert!(decoder.take_notifications().is_empty());
+        assert!(decoder.notifications.is_none());
+    }
+}
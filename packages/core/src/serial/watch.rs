@@ -0,0 +1,113 @@
+//! Port hot-plug events, and a watcher that finds them by polling.
+
+use std::ops::ControlFlow;
+use std::time::Duration;
+
+use super::{PortInfo, SerialPort};
+use crate::error::Result;
+use crate::system::TimeSource;
+
+/// A port appearing in or disappearing from `SerialPort::list_ports()`
+#[derive(Debug, Clone, PartialEq)]
+pub enum PortEvent {
+    Added(PortInfo),
+    Removed(PortInfo),
+}
+
+/// Events that turn the `before` listing into `after`: removals first, then
+/// additions in `after` order
+pub fn diff(before: &[PortInfo], after: &[PortInfo]) -> Vec<PortEvent> {
+    // Match identical entries one to one, so duplicates are counted
+    let mut added: Vec<&PortInfo> = after.iter().collect();
+    let mut events = Vec::new();
+    for info in before {
+        match added.iter().position(|other| *other == info) {
+            Some(index) => {
+                added.remove(index);
+            }
+            None => events.push(PortEvent::Removed(info.clone())),
+        }
+    }
+    events.extend(added.into_iter().cloned().map(PortEvent::Added));
+    events
+}
+
+/// Watch by listing ports every `interval` and reporting the differences,
+/// until `on_event` breaks. Ports present when called are not reported.
+/// A failed listing is skipped, since enumeration can fail while devices
+/// are coming and going.
+pub async fn poll<S: SerialPort, T: TimeSource>(
+    interval: Duration,
+    on_event: &mut dyn FnMut(PortEvent) -> ControlFlow<()>,
+) -> Result<()> {
+    let mut known = S::list_ports().await?;
+    loop {
+        T::sleep(interval).await;
+        let Ok(ports) = S::list_ports().await else {
+            continue;
+        };
+        for event in diff(&known, &ports) {
+            if on_event(event).is_break() {
+                return Ok(());
+            }
+        }
+        known = ports;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serial::mock::MockSerialPort;
+    use crate::serial::{PortType, SerialPortConfig};
+
+    fn port(name: &str) -> PortInfo {
+        PortInfo::new(name.to_string(), PortType::Pci, None)
+    }
+
+    #[test]
+    fn test_diff() {
+        let before = [port("a"), port("b"), port("b")];
+        let after = [port("b"), port("c")];
+        assert_eq!(
+            diff(&before, &after),
+            vec![
+                PortEvent::Removed(port("a")),
+                PortEvent::Removed(port("b")),
+                PortEvent::Added(port("c")),
+            ]
+        );
+        assert!(diff(&after, &after).is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_watch_mock_ports() {
+        let first = MockSerialPort::loopback().with_port("first".to_string());
+        let second = MockSerialPort::loopback().with_port("second".to_string());
+        first.plug_in();
+
+        let mut events = Vec::new();
+        let mut on_event = |event| {
+            events.push(event);
+            if events.len() < 2 {
+                ControlFlow::Continue(())
+            } else {
+                ControlFlow::Break(())
+            }
+        };
+        let (result, ()) = tokio::join!(MockSerialPort::watch_ports(&mut on_event), async {
+            tokio::time::sleep(Duration::from_millis(250)).await;
+            second.plug_in();
+            tokio::time::sleep(Duration::from_millis(250)).await;
+            first.unplug();
+        });
+        result.unwrap();
+        assert_eq!(
+            events,
+            vec![
+                PortEvent::Added(second.info().clone()),
+                PortEvent::Removed(first.info().clone()),
+            ]
+        );
+    }
+}
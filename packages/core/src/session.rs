@@ -0,0 +1,612 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+use tokio::sync::{broadcast, mpsc};
+
+use crate::data::Message;
+use crate::framing::Framer;
+use crate::serial::{PortInfo, SerialPort};
+use crate::system::TimeSource;
+use crate::{Error, Result};
+
+/// Number of events a slow subscriber may fall behind before it lags
+const EVENT_CAPACITY: usize = 1024;
+
+/// Events published by a running `PortSession`
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionEvent {
+    /// A frame received from the port, or a message written to it
+    Message(Message),
+    /// A read, write or framing error
+    Error(Error),
+    /// A read failed and the session is trying to reopen the port
+    Disconnected,
+    /// The port was reopened after `Disconnected`, at the given location
+    Reconnected(PortInfo),
+    /// The session ended and the port was closed
+    Closed,
+}
+
+#[derive(Debug)]
+enum Command {
+    Write(Message),
+    Close,
+}
+
+/// Cheap, clonable handle used to talk to a running `PortSession`
+#[derive(Debug, Clone)]
+pub struct SessionHandle {
+    events: broadcast::Sender<SessionEvent>,
+    raw: broadcast::Sender<Message>,
+    commands: mpsc::UnboundedSender<Command>,
+}
+
+impl SessionHandle {
+    /// Receive every event published after this call
+    pub fn subscribe(&self) -> broadcast::Receiver<SessionEvent> {
+        self.events.subscribe()
+    }
+
+    /// Receive every chunk read from the port after this call, exactly as
+    /// read and before framing, e.g. to forward the byte stream elsewhere
+    pub fn subscribe_raw(&self) -> broadcast::Receiver<Message> {
+        self.raw.subscribe()
+    }
+
+    /// Queue a message to be written to the port. It is published as a
+    /// `SessionEvent::Message` once written.
+    pub fn write(&self, message: Message) -> Result<()> {
+        self.commands
+            .send(Command::Write(message))
+            .map_err(|_| Error::WriteError("Session is closed".to_string()))
+    }
+
+    /// Ask the session to close the port and stop
+    pub fn close(&self) -> Result<()> {
+        self.commands
+            .send(Command::Close)
+            .map_err(|_| Error::CloseError("Session is closed".to_string()))
+    }
+
+    /// Whether the session has stopped running
+    pub fn is_closed(&self) -> bool {
+        self.commands.is_closed()
+    }
+}
+
+impl PartialEq for SessionHandle {
+    fn eq(&self, other: &Self) -> bool {
+        self.events.same_channel(&other.events) && self.commands.same_channel(&other.commands)
+    }
+}
+
+/// How a session reopens its port after a read fails, e.g. when a board
+/// resets and its USB device briefly disappears
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReconnectPolicy {
+    /// Wait before the first attempt; doubled after each failed attempt
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    /// Give up after this many attempts; `None` retries until closed
+    pub max_attempts: Option<u32>,
+}
+
+impl ReconnectPolicy {
+    pub fn with_initial_delay(mut self, initial_delay: Duration) -> Self {
+        self.initial_delay = initial_delay;
+        self
+    }
+
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    pub fn with_max_attempts(mut self, max_attempts: Option<u32>) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Wait before attempt number `attempt`, counting from 0
+    pub fn delay(&self, attempt: u32) -> Duration {
+        self.initial_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay)
+    }
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_millis(250),
+            max_delay: Duration::from_secs(5),
+            max_attempts: Some(20),
+        }
+    }
+}
+
+type Sleep = fn(Duration) -> Pin<Box<dyn Future<Output = ()>>>;
+
+/// A read on a clone of the port, handing the clone back with the result
+pub(crate) type ReadFuture<S> = Pin<Box<dyn Future<Output = (S, Result<Message>)>>>;
+
+/// Owns a serial port and runs its read loop.
+///
+/// The session does not spawn anything itself since `SerialPort` futures
+/// are not `Send`; the caller drives `run()` on its own executor (e.g.
+/// `dioxus::spawn`) and talks to it through the `SessionHandle`.
+pub struct PortSession<S: SerialPort> {
+    port: S,
+    framer: Option<Box<dyn Framer>>,
+    events: broadcast::Sender<SessionEvent>,
+    raw: broadcast::Sender<Message>,
+    commands: mpsc::UnboundedReceiver<Command>,
+    reconnect: Option<(ReconnectPolicy, Sleep)>,
+}
+
+impl<S: SerialPort + 'static> PortSession<S> {
+    pub fn new(port: S) -> (Self, SessionHandle) {
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+        let (raw, _) = broadcast::channel(EVENT_CAPACITY);
+        let (command_tx, commands) = mpsc::unbounded_channel();
+        let handle = SessionHandle {
+            events: events.clone(),
+            raw: raw.clone(),
+            commands: command_tx,
+        };
+        let session = Self {
+            port,
+            framer: None,
+            events,
+            raw,
+            commands,
+            reconnect: None,
+        };
+        (session, handle)
+    }
+
+    /// Split incoming data into frames instead of publishing raw chunks
+    pub fn with_framer(mut self, framer: impl Framer + 'static) -> Self {
+        self.framer = Some(Box::new(framer));
+        self
+    }
+
+    /// Reopen the port according to `policy` when a read fails, instead of
+    /// ending the session. `T` times the waits between attempts.
+    pub fn with_reconnect<T: TimeSource>(mut self, policy: ReconnectPolicy) -> Self {
+        self.reconnect = Some((policy, T::sleep));
+        self
+    }
+
+    /// Open the port if needed and pump data until closed or a read fails.
+    /// Always ends by publishing `SessionEvent::Closed`.
+    pub async fn run(mut self) {
+        if !self.port.is_open() {
+            if let Err(e) = self.port.open().await {
+                self.publish(SessionEvent::Error(e));
+                self.publish(SessionEvent::Closed);
+                return;
+            }
+        }
+
+        while self.pump().await.is_err() && self.reconnect().await {}
+
+        self.flush_framer();
+        if let Err(e) = self.port.close().await {
+            self.publish(SessionEvent::Error(e));
+        }
+        self.commands.close();
+        self.publish(SessionEvent::Closed);
+    }
+
+    /// Handle commands and reads until closed (`Ok`) or a read fails
+    /// (`Err`, already published)
+    async fn pump(&mut self) -> Result<()> {
+        // Reads run on a clone so a pending read survives while commands
+        // are handled on `self.port`.
+        let mut read = read_next(self.port.clone());
+        loop {
+            tokio::select! {
+                biased;
+                command = self.commands.recv() => match command {
+                    Some(Command::Write(message)) => match self.port.write(message.clone()).await {
+                        Ok(()) => self.publish(SessionEvent::Message(message)),
+                        Err(e) => self.publish(SessionEvent::Error(e)),
+                    },
+                    Some(Command::Close) | None => return Ok(()),
+                },
+                (port, result) = &mut read => {
+                    match result {
+                        Ok(chunk) => self.handle_chunk(chunk),
+                        Err(Error::Timeout(_)) => {}
+                        Err(e) => {
+                            self.publish(SessionEvent::Error(e.clone()));
+                            return Err(e);
+                        }
+                    }
+                    read = read_next(port);
+                }
+            }
+        }
+    }
+
+    /// Try to reopen the port after a failed read. Returns whether it is
+    /// open again; `false` if reconnecting is off, gave up or was closed.
+    async fn reconnect(&mut self) -> bool {
+        let Some((policy, sleep)) = self.reconnect.clone() else {
+            return false;
+        };
+        self.flush_framer();
+        let _ = self.port.close().await;
+        self.publish(SessionEvent::Disconnected);
+
+        let mut attempt = 0;
+        while policy.max_attempts.is_none_or(|max| attempt < max) {
+            let mut wait = sleep(policy.delay(attempt));
+            attempt += 1;
+            loop {
+                tokio::select! {
+                    biased;
+                    command = self.commands.recv() => match command {
+                        Some(Command::Write(_)) => self.publish(SessionEvent::Error(
+                            Error::WriteError("Port is disconnected".to_string()),
+                        )),
+                        Some(Command::Close) | None => return false,
+                    },
+                    _ = &mut wait => break,
+                }
+            }
+            if self.reopen().await.is_ok() {
+                self.publish(SessionEvent::Reconnected(self.port.info().clone()));
+                return true;
+            }
+        }
+        self.publish(SessionEvent::Error(Error::DeviceNotFound(format!(
+            "{} did not come back after {} attempts",
+            self.port.info().port,
+            attempt
+        ))));
+        false
+    }
+
+    /// Find the same device again, possibly under a new path, and open it
+    async fn reopen(&mut self) -> Result<()> {
+        let info = self.port.info().clone();
+        let ports = S::list_ports().await?;
+        let found = ports
+            .iter()
+            .filter(|candidate| info.same_device(candidate))
+            .min_by_key(|candidate| candidate.port != info.port)
+            .or_else(|| {
+                // A device without a serial number may come back under a new
+                // path; follow it only if no other device looks the same
+                let mut models = ports.iter().filter(|candidate| info.same_model(candidate));
+                match (models.next(), models.next()) {
+                    (Some(only), None) => Some(only),
+                    _ => None,
+                }
+            });
+        let mut port = match found {
+            Some(found) => self.port.clone().with_port(found.port.clone()),
+            // Ports that are never listed, e.g. network ports, are reopened
+            // where they were
+            None if !info.is_usb() => self.port.clone(),
+            None => return Err(Error::DeviceNotFound(info.port)),
+        };
+        port.open().await?;
+        self.port = port;
+        Ok(())
+    }
+
+    fn flush_framer(&mut self) {
+        match self.framer.as_mut().and_then(|f| f.flush()) {
+            Some(Ok(frame)) => self.publish(SessionEvent::Message(frame)),
+            Some(Err(e)) => self.publish(SessionEvent::Error(e)),
+            None => {}
+        }
+    }
+
+    fn handle_chunk(&mut self, chunk: Message) {
+        if chunk.bytes().is_empty() {
+            return;
+        }
+        if self.raw.receiver_count() > 0 {
+            let _ = self.raw.send(chunk.clone());
+        }
+        let Some(framer) = self.framer.as_mut() else {
+            self.publish(SessionEvent::Message(chunk));
+            return;
+        };
+        for frame in framer.push(&chunk) {
+            let event = match frame {
+                Ok(message) => SessionEvent::Message(message),
+                Err(e) => SessionEvent::Error(e),
+            };
+            // `publish` would borrow all of `self` while `framer` is borrowed
+            let _ = self.events.send(event);
+        }
+    }
+
+    fn publish(&self, event: SessionEvent) {
+        // Having no subscribers is not an error
+        let _ = self.events.send(event);
+    }
+}
+
+pub(crate) fn read_next<S: SerialPort + 'static>(mut port: S) -> ReadFuture<S> {
+    Box::pin(async move {
+        let result = port.read().await;
+        (port, result)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::sync::broadcast::error::TryRecvError;
+
+    use super::*;
+    use crate::data::{Direction, Timestamp};
+    use crate::framing::{slip, Checksum, ChecksumFramer, LineFramer, SlipFramer};
+    use crate::serial::mock::{MockSerialPort, MockTimeSource, ScriptChunk};
+    use crate::serial::{PortConfig, PortType, SerialPortConfig};
+
+    fn drain(events: &mut broadcast::Receiver<SessionEvent>) -> Vec<SessionEvent> {
+        let mut drained = Vec::new();
+        loop {
+            match events.try_recv() {
+                Ok(event) => drained.push(event),
+                Err(TryRecvError::Empty) => return drained,
+                Err(e) => panic!("{:?}", e),
+            }
+        }
+    }
+
+    fn texts(events: &[SessionEvent]) -> Vec<&str> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                SessionEvent::Message(message) => Some(message.text()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_frames_scripted_input() {
+        let port = MockSerialPort::scripted([
+            ScriptChunk::new(Duration::from_millis(10), "temp=1\nhu"),
+            ScriptChunk::new(Duration::from_millis(10), "m=2\n"),
+        ]);
+        let (session, handle) = PortSession::new(port.clone());
+        let mut events = handle.subscribe();
+        let mut raw = handle.subscribe_raw();
+
+        session.with_framer(LineFramer::default()).run().await;
+
+        let events = drain(&mut events);
+        assert_eq!(texts(&events), vec!["temp=1", "hum=2"]);
+        // Raw subscribers see the chunks as read, delimiters included
+        assert_eq!(raw.try_recv().unwrap().text(), "temp=1\nhu");
+        assert_eq!(raw.try_recv().unwrap().text(), "m=2\n");
+        // The exhausted script ends the session like a failed read
+        assert!(matches!(
+            events[events.len() - 2],
+            SessionEvent::Error(Error::ReadError(_))
+        ));
+        assert_eq!(events.last(), Some(&SessionEvent::Closed));
+        assert!(handle.is_closed());
+        assert!(!port.is_open());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_write_and_close() {
+        let port = MockSerialPort::loopback();
+        let (session, handle) = PortSession::new(port.clone());
+        let mut events = handle.subscribe();
+
+        tokio::join!(session.with_framer(LineFramer::default()).run(), async {
+            let message = Message::new(Timestamp(1), Direction::Out, "ping\n");
+            handle.write(message).unwrap();
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            handle.close().unwrap();
+        });
+
+        let events = drain(&mut events);
+        let directions: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event {
+                SessionEvent::Message(message) => Some(message.direction()),
+                _ => None,
+            })
+            .collect();
+        // The write is published, then read back through the loopback
+        assert_eq!(directions, vec![Direction::Out, Direction::In]);
+        assert_eq!(texts(&events), vec!["ping\n", "ping"]);
+        assert_eq!(events.last(), Some(&SessionEvent::Closed));
+        assert_eq!(port.writes().len(), 1);
+        assert!(handle
+            .write(Message::new(Timestamp(2), Direction::Out, "x"))
+            .is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_bad_trailing_frame_is_reported() {
+        let port = MockSerialPort::scripted([ScriptChunk::new(Duration::ZERO, "$GPTXT,a*00")]);
+        let (session, handle) = PortSession::new(port);
+        let mut events = handle.subscribe();
+
+        let framer = ChecksumFramer::new(LineFramer::default(), Checksum::NmeaXor);
+        session.with_framer(framer).run().await;
+
+        let events = drain(&mut events);
+        assert!(texts(&events).is_empty());
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, SessionEvent::Error(Error::ParseError(_)))));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_framing_errors_do_not_end_session() {
+        let mut input = vec![slip::END, 1, slip::ESC, 0x00, slip::END];
+        input.extend(slip::encode(b"ok"));
+        let port = MockSerialPort::scripted([ScriptChunk::new(Duration::ZERO, input)]);
+        let (session, handle) = PortSession::new(port);
+        let mut events = handle.subscribe();
+
+        session.with_framer(SlipFramer::new()).run().await;
+
+        let events = drain(&mut events);
+        assert!(matches!(
+            events[0],
+            SessionEvent::Error(Error::ParseError(_))
+        ));
+        assert_eq!(texts(&events), vec!["ok"]);
+    }
+
+    #[tokio::test]
+    async fn test_open_failure() {
+        let port = MockSerialPort::loopback().with_config(PortConfig::new(0, 8, 1));
+        let (session, handle) = PortSession::new(port);
+        let mut events = handle.subscribe();
+
+        session.run().await;
+
+        let events = drain(&mut events);
+        assert!(matches!(
+            events[0],
+            SessionEvent::Error(Error::ConfigError(_))
+        ));
+        assert_eq!(events[1], SessionEvent::Closed);
+    }
+
+    #[test]
+    fn test_reconnect_backoff() {
+        let policy = ReconnectPolicy::default();
+        let delays: Vec<_> = (0..7).map(|attempt| policy.delay(attempt)).collect();
+        assert_eq!(
+            delays,
+            [250, 500, 1000, 2000, 4000, 5000, 5000].map(Duration::from_millis)
+        );
+        assert_eq!(policy.delay(u32::MAX), policy.max_delay);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_reconnect_finds_moved_device() {
+        let info = PortInfo::new(
+            "/dev/ttyACM0".to_string(),
+            PortType::Usb {
+                vendor_id: Some(0x2341),
+                product_id: Some(0x0043),
+                product_name: None,
+                manufacturer: None,
+                serial_number: Some("8573".to_string()),
+            },
+            None,
+        );
+        let port = MockSerialPort::loopback().with_info(info);
+        port.plug_in();
+        let (session, handle) = PortSession::new(port.clone());
+        let mut events = handle.subscribe();
+
+        let session = session
+            .with_framer(LineFramer::default())
+            .with_reconnect::<MockTimeSource>(ReconnectPolicy::default());
+        tokio::join!(session.run(), async {
+            port.push_input(b"before\npartial");
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            port.unplug();
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            // Re-enumerated under another path
+            port.clone().with_port("/dev/ttyACM1".to_string()).plug_in();
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            port.push_input(b"after\n");
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            handle.close().unwrap();
+        });
+
+        let events = drain(&mut events);
+        assert_eq!(texts(&events), vec!["before", "partial", "after"]);
+        assert!(matches!(
+            events[1],
+            SessionEvent::Error(Error::ReadError(_))
+        ));
+        // The partial frame is flushed before the marker
+        assert_eq!(events[3], SessionEvent::Disconnected);
+        let SessionEvent::Reconnected(info) = &events[4] else {
+            panic!("expected Reconnected, got {:?}", events[4]);
+        };
+        assert_eq!(info.port, "/dev/ttyACM1");
+        assert_eq!(events.last(), Some(&SessionEvent::Closed));
+    }
+
+    #[tokio::test]
+    async fn test_reopen_without_serial_number() {
+        let board = |port: &str| {
+            let info = PortInfo::new(
+                port.to_string(),
+                PortType::Usb {
+                    vendor_id: Some(0x2341),
+                    product_id: Some(0x0043),
+                    product_name: None,
+                    manufacturer: None,
+                    serial_number: None,
+                },
+                None,
+            );
+            MockSerialPort::loopback().with_info(info)
+        };
+        let port = board("/dev/ttyACM0");
+        let (mut session, _handle) = PortSession::new(port.clone());
+
+        // Two identical boards: neither can be told to be the lost one
+        port.clone().with_port("/dev/ttyACM1".to_string()).plug_in();
+        let other = board("/dev/ttyACM2");
+        other.plug_in();
+        assert!(matches!(
+            session.reopen().await,
+            Err(Error::DeviceNotFound(_))
+        ));
+
+        other.unplug();
+        session.reopen().await.unwrap();
+        assert_eq!(session.port.info().port, "/dev/ttyACM1");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_reconnect_gives_up() {
+        let port = MockSerialPort::loopback();
+        let (session, handle) = PortSession::new(port.clone());
+        let mut events = handle.subscribe();
+
+        let policy = ReconnectPolicy::default().with_max_attempts(Some(3));
+        tokio::join!(
+            session.with_reconnect::<MockTimeSource>(policy).run(),
+            async {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                port.unplug();
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                let message = Message::new(Timestamp(1), Direction::Out, "x");
+                handle.write(message).unwrap();
+            }
+        );
+
+        let events = drain(&mut events);
+        assert_eq!(
+            events[..3],
+            [
+                SessionEvent::Error(Error::ReadError("Mock device was unplugged".to_string())),
+                SessionEvent::Disconnected,
+                SessionEvent::Error(Error::WriteError("Port is disconnected".to_string())),
+            ]
+        );
+        assert!(matches!(
+            events[3],
+            SessionEvent::Error(Error::DeviceNotFound(_))
+        ));
+        assert_eq!(events[4], SessionEvent::Closed);
+        assert!(handle.is_closed());
+    }
+}
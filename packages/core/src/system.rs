@@ -1,3 +1,7 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
 use crate::data::Timestamp;
 
 /// System utilities and abstractions used across crates.
@@ -9,4 +13,7 @@ use crate::data::Timestamp;
 pub trait TimeSource: PartialEq + Clone {
     /// Return current Unix timestamp in milliseconds.
     fn now_millis() -> Timestamp;
+
+    /// Wait for `duration` without blocking the executor.
+    fn sleep(duration: Duration) -> Pin<Box<dyn Future<Output = ()>>>;
 }
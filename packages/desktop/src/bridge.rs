@@ -0,0 +1,306 @@
+//! Share a port Sermo holds open with other programs over TCP.
+//!
+//! Every chunk read from the port is sent to every connected client, and
+//! bytes from clients are written to the port through its session, so the
+//! Sermo terminal keeps showing all traffic.
+
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::{JoinHandle, JoinSet};
+
+use project_core::{
+    data::{Direction, Message},
+    session::{SessionEvent, SessionHandle},
+    Error, Result, TimeSource,
+};
+
+use crate::system::SystemTimeSource;
+
+/// Which clients may write to the port
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WriteMode {
+    /// Writes from every client are merged into the port
+    #[default]
+    Shared,
+    /// The first client to write keeps the port until it disconnects;
+    /// writes from the others are dropped
+    Exclusive,
+}
+
+/// State shared by the accept loop and its clients
+#[derive(Debug, Default)]
+struct Shared {
+    clients: AtomicUsize,
+    next_id: AtomicUsize,
+    /// Client holding the port in `WriteMode::Exclusive`
+    writer: Mutex<Option<usize>>,
+}
+
+/// A running bridge. It stops when dropped or when the session closes.
+#[derive(Debug)]
+pub struct Bridge {
+    local_addr: SocketAddr,
+    mode: WriteMode,
+    shared: Arc<Shared>,
+    task: JoinHandle<()>,
+}
+
+impl Bridge {
+    /// Listen on `address` (e.g. `127.0.0.1:7000`, or port 0 for any free
+    /// port) and bridge clients to `session`
+    pub async fn start(session: SessionHandle, address: &str, mode: WriteMode) -> Result<Self> {
+        if session.is_closed() {
+            return Err(Error::OpenError("Session is closed".to_string()));
+        }
+        let listener = TcpListener::bind(address)
+            .await
+            .map_err(|e| Error::OpenError(format!("{}: {}", address, e)))?;
+        let local_addr = listener
+            .local_addr()
+            .map_err(|e| Error::OpenError(e.to_string()))?;
+        let shared = Arc::new(Shared::default());
+        let task = tokio::spawn(accept_loop(listener, session, mode, shared.clone()));
+        Ok(Self {
+            local_addr,
+            mode,
+            shared,
+            task,
+        })
+    }
+
+    /// Address clients connect to
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    pub fn mode(&self) -> WriteMode {
+        self.mode
+    }
+
+    /// Number of connected clients
+    pub fn clients(&self) -> usize {
+        self.shared.clients.load(Ordering::Relaxed)
+    }
+
+    /// Whether the bridge stopped because its session closed
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+}
+
+impl Drop for Bridge {
+    fn drop(&mut self) {
+        // Dropping the accept loop drops its `JoinSet`, aborting clients
+        self.task.abort();
+    }
+}
+
+async fn accept_loop(
+    listener: TcpListener,
+    session: SessionHandle,
+    mode: WriteMode,
+    shared: Arc<Shared>,
+) {
+    let mut events = session.subscribe();
+    let mut clients = JoinSet::new();
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                // A failed accept only affects that client
+                if let Ok((stream, _)) = accepted {
+                    clients.spawn(serve_client(stream, session.clone(), mode, shared.clone()));
+                }
+            }
+            event = events.recv() => match event {
+                Ok(SessionEvent::Closed) | Err(RecvError::Closed) => break,
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+            },
+            Some(_) = clients.join_next() => {}
+        }
+    }
+}
+
+/// Counts a client while it is connected and releases its exclusive
+/// write access, even if its task is aborted
+struct ClientGuard {
+    id: usize,
+    shared: Arc<Shared>,
+}
+
+impl ClientGuard {
+    fn new(shared: Arc<Shared>) -> Self {
+        shared.clients.fetch_add(1, Ordering::Relaxed);
+        let id = shared.next_id.fetch_add(1, Ordering::Relaxed);
+        Self { id, shared }
+    }
+
+    fn may_write(&self, mode: WriteMode) -> bool {
+        match mode {
+            WriteMode::Shared => true,
+            WriteMode::Exclusive => {
+                let mut writer = self.shared.writer.lock().unwrap();
+                *writer.get_or_insert(self.id) == self.id
+            }
+        }
+    }
+}
+
+impl Drop for ClientGuard {
+    fn drop(&mut self) {
+        self.shared.clients.fetch_sub(1, Ordering::Relaxed);
+        let mut writer = self.shared.writer.lock().unwrap();
+        if *writer == Some(self.id) {
+            *writer = None;
+        }
+    }
+}
+
+async fn serve_client(
+    stream: TcpStream,
+    session: SessionHandle,
+    mode: WriteMode,
+    shared: Arc<Shared>,
+) {
+    let guard = ClientGuard::new(shared);
+    let mut incoming = session.subscribe_raw();
+    let (mut reader, mut writer) = stream.into_split();
+    let mut buf = [0u8; 1024];
+    loop {
+        tokio::select! {
+            chunk = incoming.recv() => match chunk {
+                Ok(chunk) => {
+                    if writer.write_all(chunk.bytes()).await.is_err() {
+                        break;
+                    }
+                }
+                // A slow client misses data rather than stalling the port
+                Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            },
+            read = reader.read(&mut buf) => match read {
+                Ok(0) | Err(_) => break,
+                Ok(read) => {
+                    if !guard.may_write(mode) {
+                        continue;
+                    }
+                    let message =
+                        Message::new(SystemTimeSource::now_millis(), Direction::Out, &buf[..read]);
+                    if session.write(message).is_err() {
+                        break;
+                    }
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use project_core::{serial::mock::MockSerialPort, session::PortSession};
+
+    use super::*;
+
+    async fn connect(bridge: &Bridge, clients: usize) -> TcpStream {
+        let stream = TcpStream::connect(bridge.local_addr()).await.unwrap();
+        while bridge.clients() < clients {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        stream
+    }
+
+    async fn read(stream: &mut TcpStream, len: usize) -> Vec<u8> {
+        let mut data = vec![0u8; len];
+        tokio::time::timeout(Duration::from_secs(5), stream.read_exact(&mut data))
+            .await
+            .unwrap()
+            .unwrap();
+        data
+    }
+
+    /// Run `test` against a bridge over a loopback port, which echoes every
+    /// write back as input
+    async fn with_bridge<F: std::future::Future<Output = ()>>(
+        mode: WriteMode,
+        test: impl FnOnce(Bridge, SessionHandle) -> F,
+    ) {
+        let (session, handle) = PortSession::new(MockSerialPort::loopback());
+        let bridge = Bridge::start(handle.clone(), "127.0.0.1:0", mode)
+            .await
+            .unwrap();
+        tokio::join!(session.run(), async {
+            test(bridge, handle.clone()).await;
+            let _ = handle.close();
+        });
+    }
+
+    #[tokio::test]
+    async fn test_fan_out_and_merged_writes() {
+        with_bridge(WriteMode::Shared, |bridge, handle| async move {
+            let mut events = handle.subscribe();
+            let mut a = connect(&bridge, 1).await;
+            let mut b = connect(&bridge, 2).await;
+
+            a.write_all(b"one").await.unwrap();
+            assert_eq!(read(&mut a, 3).await, b"one");
+            assert_eq!(read(&mut b, 3).await, b"one");
+            b.write_all(b"two").await.unwrap();
+            assert_eq!(read(&mut a, 3).await, b"two");
+
+            // The session still publishes the client's write for the terminal
+            let event = events.recv().await.unwrap();
+            assert!(matches!(
+                event,
+                SessionEvent::Message(m) if m.direction() == Direction::Out && m.text() == "one"
+            ));
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_exclusive_writer() {
+        with_bridge(WriteMode::Exclusive, |bridge, _| async move {
+            let mut a = connect(&bridge, 1).await;
+            let mut b = connect(&bridge, 2).await;
+
+            a.write_all(b"a").await.unwrap();
+            assert_eq!(read(&mut b, 1).await, b"a");
+            // `a` holds the port, so `b` is ignored
+            b.write_all(b"b").await.unwrap();
+            a.write_all(b"c").await.unwrap();
+            assert_eq!(read(&mut b, 1).await, b"c");
+
+            drop(a);
+            while bridge.clients() > 1 {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+            b.write_all(b"d").await.unwrap();
+            assert_eq!(read(&mut b, 1).await, b"d");
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_session_close_stops_bridge() {
+        let (session, handle) = PortSession::new(MockSerialPort::loopback());
+        let bridge = Bridge::start(handle.clone(), "127.0.0.1:0", WriteMode::Shared)
+            .await
+            .unwrap();
+        tokio::join!(session.run(), async {
+            let mut client = connect(&bridge, 1).await;
+            handle.close().unwrap();
+            let mut buf = [0u8; 1];
+            assert_eq!(client.read(&mut buf).await.unwrap(), 0);
+        });
+        assert!(bridge.is_finished());
+        assert!(Bridge::start(handle, "127.0.0.1:0", WriteMode::Shared)
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use dioxus::prelude::*;
+use uuid::Uuid;
+
+use ui::SerialContext;
+
+use crate::bridge::{Bridge, WriteMode};
+
+/// Share open ports with other programs over TCP
+#[allow(non_snake_case)]
+#[component]
+pub fn BridgePanel() -> Element {
+    let serial_context = use_context::<SerialContext>();
+    let port_list = serial_context.port_list;
+    let sessions = serial_context.sessions;
+
+    let mut bridges = use_signal(HashMap::<Uuid, Bridge>::new);
+    let mut port = use_signal(String::new);
+    let mut address = use_signal(|| "127.0.0.1:7000".to_string());
+    let mut exclusive = use_signal(|| false);
+    let mut error = use_signal(|| None::<String>);
+
+    // Forget bridges whose port was closed; they have stopped already
+    use_effect(move || {
+        let sessions = sessions.read();
+        bridges
+            .write()
+            .retain(|id, bridge| sessions.contains_key(id) && !bridge.is_finished());
+    });
+
+    // Client counts change off the UI thread; re-render to show them
+    let mut tick = use_signal(|| 0u64);
+    use_future(move || async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            if !bridges.peek().is_empty() {
+                tick += 1;
+            }
+        }
+    });
+
+    // Subscribe to the refresh tick
+    tick.read();
+
+    let start = move |_| {
+        let Some((id, handle)) = port
+            .read()
+            .parse::<Uuid>()
+            .ok()
+            .and_then(|id| Some((id, sessions.read().get(&id)?.clone())))
+        else {
+            error.set(Some("Choose an open port".to_string()));
+            return;
+        };
+        let address = address.read().trim().to_string();
+        let mode = if exclusive() {
+            WriteMode::Exclusive
+        } else {
+            WriteMode::Shared
+        };
+        spawn(async move {
+            match Bridge::start(handle, &address, mode).await {
+                Ok(bridge) => {
+                    bridges.write().insert(id, bridge);
+                    error.set(None);
+                }
+                Err(e) => error.set(Some(e.to_string())),
+            }
+        });
+    };
+
+    rsx!(
+        div { class: "bridge-panel",
+            h4 { "Share over TCP" }
+            label { "Port"
+                select {
+                    value: "{port}",
+                    onchange: move |e| port.set(e.value()),
+                    option { value: "", "Choose an open port" }
+                    for (id, info) in port_list.read().iter() {
+                        option { value: "{id}", "{info.port}" }
+                    }
+                }
+            }
+            label { "Listen on"
+                input { value: "{address}", oninput: move |e| address.set(e.value()) }
+            }
+            label {
+                input {
+                    r#type: "checkbox",
+                    checked: exclusive(),
+                    onchange: move |e| exclusive.set(e.checked()),
+                }
+                " Exclusive writer"
+            }
+            button { onclick: start, "Share" }
+            if let Some(error) = error() {
+                p { class: "settings-error", "{error}" }
+            }
+            ul {
+                for (id, bridge) in bridges.read().iter() {
+                    li { key: "{id}",
+                        {
+                            let name = port_list
+                                .read()
+                                .get(id)
+                                .map(|info| info.port.clone())
+                                .unwrap_or_default();
+                            let mode = match bridge.mode() {
+                                WriteMode::Shared => "shared",
+                                WriteMode::Exclusive => "exclusive writer",
+                            };
+                            let clients = bridge.clients();
+                            rsx!("{name} on {bridge.local_addr()} ({mode}, {clients} clients) ")
+                        }
+                        button {
+                            onclick: {
+                                let id = *id;
+                                move |_| {
+                                    bridges.write().remove(&id);
+                                }
+                            },
+                            "Stop"
+                        }
+                    }
+                }
+            }
+        }
+    )
+}
@@ -1,3 +1,5 @@
+mod bridge;
+mod bridge_panel;
 mod serial;
 mod system;
 
@@ -5,6 +7,7 @@ use dioxus::prelude::*;
 
 use ui::App;
 
+use bridge_panel::BridgePanel;
 use serial::DesktopSerialPort;
 use system::SystemTimeSource;
 
@@ -20,6 +23,8 @@ fn DesktopApp() -> Element {
     rsx! {
         document::Link { rel: "stylesheet", href: MAIN_CSS }
 
-        App::<DesktopSerialPort, SystemTimeSource> {}
+        App::<DesktopSerialPort, SystemTimeSource> {
+            BridgePanel {}
+        }
     }
 }
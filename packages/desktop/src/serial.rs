@@ -1,16 +1,76 @@
 use async_trait::async_trait;
 use project_core::{
     data::{Direction, Message},
-    serial::{PortConfig, PortInfo, SerialPort, SerialPortConfig},
+    serial::{
+        watch::{self, PortEvent},
+        FlowControl, Parity, PortConfig, PortInfo, PortType, SerialPort, SerialPortConfig,
+    },
     Error, Result,
 };
-use std::sync::{Arc, Mutex};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::fmt::Debug;
+use std::io::{self, Read, Write};
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, TryLockError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-/// Desktop implementation of SerialPort using the serialport crate
+mod network;
+
+use crate::system::SystemTimeSource;
+use network::NetworkConnection;
+
+/// How often `watch_ports` re-enumerates
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Name under which `list_ports` offers to create a virtual PTY port
+pub const VIRTUAL_PORT: &str = "Virtual PTY";
+
+/// The PTY pair behind a virtual port
+#[derive(Debug)]
+struct VirtualPty {
+    /// Master end, taken by `open`
+    master: Option<Box<dyn serialport::SerialPort>>,
+    /// Slave end, held so the pair survives other programs opening and
+    /// closing the slave path
+    _slave: Box<dyn serialport::SerialPort>,
+}
+
+/// An open link to the device: a local port or a network connection
+trait Connection: Read + Write + Send + Debug {
+    /// A second handle to the same link, so writes need not wait for a
+    /// blocked read
+    fn try_clone(&self) -> io::Result<Box<dyn Connection>>;
+}
+
+impl Connection for Box<dyn serialport::SerialPort> {
+    fn try_clone(&self) -> io::Result<Box<dyn Connection>> {
+        let port = serialport::SerialPort::try_clone(self.as_ref())?;
+        Ok(Box::new(port))
+    }
+}
+
+impl Connection for NetworkConnection {
+    fn try_clone(&self) -> io::Result<Box<dyn Connection>> {
+        Ok(Box::new(NetworkConnection::try_clone(self)?))
+    }
+}
+
+/// A connection shared between the port and its blocking tasks
+type SharedConnection = Arc<Mutex<Option<Box<dyn Connection>>>>;
+
+/// Desktop implementation of SerialPort using the serialport crate, or a
+/// TCP connection for `PortType::Network`
 #[derive(Debug, Clone)]
 pub struct DesktopSerialPort {
-    port: Arc<Mutex<Option<Box<dyn serialport::SerialPort>>>>,
+    /// Handle `read` holds while waiting for data
+    port: SharedConnection,
+    /// Handle for `write` and `flush`, so they are not stuck behind a read
+    writer: SharedConnection,
+    /// Kept apart from the handles, whose locks a blocked read or write
+    /// may hold for up to its timeout
+    is_open: Arc<AtomicBool>,
+    /// Set for virtual ports, which are not opened by name
+    virtual_pty: Option<Arc<Mutex<VirtualPty>>>,
     info: PortInfo,
     config: PortConfig,
 }
@@ -25,10 +85,63 @@ impl DesktopSerialPort {
     pub fn new(info: PortInfo, config: PortConfig) -> Self {
         Self {
             port: Arc::new(Mutex::new(None)),
+            writer: Arc::new(Mutex::new(None)),
+            is_open: Arc::new(AtomicBool::new(false)),
+            virtual_pty: None,
             info,
             config,
         }
     }
+
+    /// Create a pseudo-terminal pair and return a port on its master end.
+    /// `info().port` is the slave path (e.g. `/dev/pts/3`) that other
+    /// programs open to act as the device.
+    #[cfg(unix)]
+    pub fn virtual_pty(config: PortConfig) -> Result<Self> {
+        let (master, slave) =
+            serialport::TTYPort::pair().map_err(|e| Error::OpenError(e.to_string()))?;
+        let path = serialport::SerialPort::name(&slave)
+            .ok_or_else(|| Error::OpenError("PTY slave has no path".to_string()))?;
+        let info = PortInfo::new(
+            path.clone(),
+            PortType::Other(VIRTUAL_PORT.to_string()),
+            Some(format!("{} (device end)", VIRTUAL_PORT)),
+        );
+        Ok(Self {
+            virtual_pty: Some(Arc::new(Mutex::new(VirtualPty {
+                master: Some(Box::new(master)),
+                _slave: Box::new(slave),
+            }))),
+            ..Self::new(info, config)
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn virtual_pty(_config: PortConfig) -> Result<Self> {
+        Err(Error::OpenError(
+            "Virtual PTY ports are only available on Unix".to_string(),
+        ))
+    }
+
+    /// Keep `connection` for reading and a clone of it for writing
+    fn attach(&mut self, connection: Box<dyn Connection>) -> Result<()> {
+        let writer = connection
+            .try_clone()
+            .map_err(|e| Error::OpenError(e.to_string()))?;
+        *self.port.lock().unwrap() = Some(connection);
+        *self.writer.lock().unwrap() = Some(writer);
+        self.is_open.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    /// Entry `list_ports` adds so a virtual port can be created from the UI
+    pub fn virtual_port_info() -> PortInfo {
+        PortInfo::new(
+            VIRTUAL_PORT.to_string(),
+            PortType::Other(VIRTUAL_PORT.to_string()),
+            Some("Create a pseudo-terminal pair".to_string()),
+        )
+    }
 }
 
 impl SerialPortConfig for DesktopSerialPort {
@@ -45,11 +158,47 @@ impl SerialPortConfig for DesktopSerialPort {
 
 #[async_trait(?Send)]
 impl SerialPort for DesktopSerialPort {
+    const NETWORK_PORTS: bool = true;
+
+    async fn list_ports() -> Result<Vec<PortInfo>> {
+        let ports = serialport::available_ports().map_err(|e| Error::SerialError(e.to_string()))?;
+        let mut ports: Vec<PortInfo> = ports.into_iter().map(to_port_info).collect();
+        if cfg!(unix) {
+            ports.push(Self::virtual_port_info());
+        }
+        Ok(ports)
+    }
+
+    /// Polls the platform enumeration (udev on Linux)
+    async fn watch_ports(on_event: &mut dyn FnMut(PortEvent) -> ControlFlow<()>) -> Result<()> {
+        watch::poll::<Self, SystemTimeSource>(WATCH_INTERVAL, on_event).await
+    }
+
     async fn request_port(info: PortInfo, config: PortConfig) -> Result<Self> {
+        if info.port == VIRTUAL_PORT {
+            return Self::virtual_pty(config);
+        }
         Ok(DesktopSerialPort::new(info, config))
     }
 
     async fn open(&mut self) -> Result<()> {
+        self.config.validate()?;
+
+        if let Some(pty) = &self.virtual_pty {
+            let mut master = pty.lock().unwrap().master.take().ok_or_else(|| {
+                Error::OpenError("Virtual PTY was closed; create a new one".to_string())
+            })?;
+            master
+                .set_timeout(self.config.read_timeout)
+                .map_err(|e| Error::OpenError(e.to_string()))?;
+            return self.attach(Box::new(master));
+        }
+
+        if let PortType::Network { rfc2217 } = self.info.port_type {
+            let connection = NetworkConnection::connect(&self.info.port, rfc2217, &self.config)?;
+            return self.attach(Box::new(connection));
+        }
+
         let port = serialport::new(&self.info.port, self.config.baud_rate)
             .data_bits(match self.config.data_bits {
                 5 => serialport::DataBits::Five,
@@ -63,32 +212,72 @@ impl SerialPort for DesktopSerialPort {
                 2 => serialport::StopBits::Two,
                 _ => return Err(Error::ConfigError("Invalid stop bits".to_string())),
             })
+            .parity(match self.config.parity {
+                Parity::None => serialport::Parity::None,
+                Parity::Odd => serialport::Parity::Odd,
+                Parity::Even => serialport::Parity::Even,
+            })
+            .flow_control(match self.config.flow_control {
+                FlowControl::None => serialport::FlowControl::None,
+                FlowControl::Software => serialport::FlowControl::Software,
+                FlowControl::Hardware => serialport::FlowControl::Hardware,
+            })
+            .timeout(self.config.read_timeout)
             .open()
             .map_err(|e| Error::OpenError(e.to_string()))?;
 
-        *self.port.lock().unwrap() = Some(port);
-        Ok(())
+        self.attach(Box::new(port))
     }
 
+    /// Returns without waiting for a blocked read or write; that task
+    /// drops its handle once it sees the port closed
     async fn close(&mut self) -> Result<()> {
-        *self.port.lock().unwrap() = None;
+        self.is_open.store(false, Ordering::Release);
+        for handle in [&self.port, &self.writer] {
+            match handle.try_lock() {
+                Ok(mut connection) => *connection = None,
+                Err(TryLockError::Poisoned(e)) => *e.into_inner() = None,
+                Err(TryLockError::WouldBlock) => {}
+            }
+        }
         Ok(())
     }
 
     async fn read(&mut self) -> Result<Message> {
-        let mut port_lock = self.port.lock().unwrap();
-        let port = port_lock
-            .as_mut()
-            .ok_or_else(|| Error::ReadError("Port not open".to_string()))?;
+        let port = self.port.clone();
+        let is_open = self.is_open.clone();
+        let buffer_size = self.config.buffer_size;
 
-        // Read available bytes into a buffer (non-blocking simple approach)
-        let mut buf = vec![0u8; 1024];
-        let read = port
-            .read(&mut buf)
-            .map_err(|e| Error::ReadError(e.to_string()))?;
-        buf.truncate(read);
+        // `serialport` reads block for up to `read_timeout`, so keep them off
+        // the async executor.
+        let buf = tokio::task::spawn_blocking(move || {
+            let mut port_lock = port.lock().unwrap();
+            let not_open = || Error::ReadError("Port not open".to_string());
+            if !is_open.load(Ordering::Acquire) {
+                *port_lock = None;
+                return Err(not_open());
+            }
+            let connection = port_lock.as_mut().ok_or_else(not_open)?;
 
-        let text = String::from_utf8_lossy(&buf).to_string();
+            let mut buf = vec![0u8; buffer_size];
+            let read = connection.read(&mut buf);
+            // Closed while reading: `close` left the handle to us
+            if !is_open.load(Ordering::Acquire) {
+                *port_lock = None;
+                return Err(not_open());
+            }
+            let read = read.map_err(|e| {
+                if network::is_timeout(&e) {
+                    Error::Timeout(e.to_string())
+                } else {
+                    Error::ReadError(e.to_string())
+                }
+            })?;
+            buf.truncate(read);
+            Ok::<_, Error>(buf)
+        })
+        .await
+        .map_err(|e| Error::ReadError(e.to_string()))??;
 
         let ts = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -98,28 +287,46 @@ impl SerialPort for DesktopSerialPort {
         Ok(Message::new(
             project_core::data::Timestamp(ts),
             Direction::In,
-            text,
+            buf,
         ))
     }
 
     async fn write(&mut self, message: Message) -> Result<()> {
-        let mut port_lock = self.port.lock().unwrap();
-        let port = port_lock
-            .as_mut()
-            .ok_or_else(|| Error::WriteError("Port not open".to_string()))?;
+        let writer = self.writer.clone();
+        let is_open = self.is_open.clone();
+
+        // Writes can block on flow control, so they run off the executor
+        // too, on their own handle
+        tokio::task::spawn_blocking(move || {
+            let mut writer_lock = writer.lock().unwrap();
+            if !is_open.load(Ordering::Acquire) {
+                *writer_lock = None;
+            }
+            let writer = writer_lock
+                .as_mut()
+                .ok_or_else(|| Error::WriteError("Port not open".to_string()))?;
 
-        let data = message.text().as_bytes();
-        port.write(data)
-            .map(|_| ())
-            .map_err(|e| Error::WriteError(e.to_string()))
+            writer
+                .write_all(message.bytes())
+                .map_err(|e| Error::WriteError(e.to_string()))
+        })
+        .await
+        .map_err(|e| Error::WriteError(e.to_string()))?
     }
 
     async fn flush(&mut self) -> Result<()> {
-        let mut port_lock = self.port.lock().unwrap();
-        if let Some(port) = port_lock.as_mut() {
-            port.flush().map_err(|e| Error::WriteError(e.to_string()))?;
-        }
-        Ok(())
+        let writer = self.writer.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut writer_lock = writer.lock().unwrap();
+            if let Some(writer) = writer_lock.as_mut() {
+                writer
+                    .flush()
+                    .map_err(|e| Error::WriteError(e.to_string()))?;
+            }
+            Ok(())
+        })
+        .await
+        .map_err(|e| Error::WriteError(e.to_string()))?
     }
 
     fn config(&self) -> &PortConfig {
@@ -131,6 +338,175 @@ impl SerialPort for DesktopSerialPort {
     }
 
     fn is_open(&self) -> bool {
-        self.port.lock().unwrap().is_some()
+        self.is_open.load(Ordering::Acquire)
+    }
+}
+
+/// Convert `serialport` enumeration results into the core `PortInfo`
+fn to_port_info(port: serialport::SerialPortInfo) -> PortInfo {
+    match port.port_type {
+        serialport::SerialPortType::UsbPort(usb) => {
+            let description = usb.product.clone().or_else(|| usb.manufacturer.clone());
+            PortInfo::new(
+                port.port_name,
+                PortType::Usb {
+                    vendor_id: Some(usb.vid),
+                    product_id: Some(usb.pid),
+                    product_name: usb.product,
+                    manufacturer: usb.manufacturer,
+                    serial_number: usb.serial_number,
+                },
+                description,
+            )
+        }
+        serialport::SerialPortType::PciPort => PortInfo::new(port.port_name, PortType::Pci, None),
+        serialport::SerialPortType::BluetoothPort => {
+            PortInfo::new(port.port_name, PortType::Bluetooth, None)
+        }
+        serialport::SerialPortType::Unknown => {
+            PortInfo::new(port.port_name, PortType::Other("Unknown".to_string()), None)
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::fs::OpenOptions;
+    use std::io::{Read, Write};
+    use std::time::Duration;
+
+    use project_core::{
+        framing::LineFramer,
+        session::{PortSession, SessionEvent},
+    };
+
+    use super::*;
+
+    async fn virtual_port() -> DesktopSerialPort {
+        let config = PortConfig::default().with_read_timeout(Duration::from_millis(50));
+        let mut port =
+            DesktopSerialPort::request_port(DesktopSerialPort::virtual_port_info(), config)
+                .await
+                .unwrap();
+        port.open().await.unwrap();
+        port
+    }
+
+    /// Read until `len` bytes arrived, skipping read timeouts
+    async fn read_bytes(port: &mut DesktopSerialPort, len: usize) -> Vec<u8> {
+        let mut data = Vec::new();
+        while data.len() < len {
+            match port.read().await {
+                Ok(message) => data.extend_from_slice(message.bytes()),
+                Err(Error::Timeout(_)) => {}
+                Err(e) => panic!("{}", e),
+            }
+        }
+        data
+    }
+
+    #[tokio::test]
+    async fn test_virtual_pty_round_trip() {
+        let mut port = virtual_port().await;
+        assert!(port.info().port.starts_with("/dev/"));
+        let mut device = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&port.info().port)
+            .unwrap();
+
+        device.write_all(b"hello").unwrap();
+        assert_eq!(read_bytes(&mut port, 5).await, b"hello");
+
+        let message = Message::new(project_core::data::Timestamp(0), Direction::Out, "ack");
+        port.write(message).await.unwrap();
+        let mut reply = [0u8; 3];
+        device.read_exact(&mut reply).unwrap();
+        assert_eq!(&reply, b"ack");
+
+        // Nothing pending: the read times out rather than blocking
+        assert!(matches!(port.read().await, Err(Error::Timeout(_))));
+
+        port.close().await.unwrap();
+        assert!(port.open().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_write_while_reading() {
+        let config = PortConfig::default().with_read_timeout(Duration::from_secs(2));
+        let mut reader =
+            DesktopSerialPort::request_port(DesktopSerialPort::virtual_port_info(), config)
+                .await
+                .unwrap();
+        reader.open().await.unwrap();
+        let mut writer = reader.clone();
+        let mut device = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&reader.info().port)
+            .unwrap();
+
+        // The write goes out while the read is still waiting, and the
+        // device's answer ends the read before it times out
+        let (read, ()) = tokio::join!(reader.read(), async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            let message = Message::new(project_core::data::Timestamp(0), Direction::Out, "ping");
+            writer.write(message).await.unwrap();
+            let mut request = [0u8; 4];
+            device.read_exact(&mut request).unwrap();
+            assert_eq!(&request, b"ping");
+            device.write_all(b"pong").unwrap();
+        });
+        assert_eq!(read.unwrap().bytes(), b"pong");
+    }
+
+    #[tokio::test]
+    async fn test_close_while_reading() {
+        let config = PortConfig::default().with_read_timeout(Duration::from_secs(2));
+        let mut reader =
+            DesktopSerialPort::request_port(DesktopSerialPort::virtual_port_info(), config)
+                .await
+                .unwrap();
+        reader.open().await.unwrap();
+        let mut closer = reader.clone();
+
+        // Neither closing nor checking the state waits for the blocked read
+        let (read, elapsed) = tokio::join!(reader.read(), async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            let start = std::time::Instant::now();
+            closer.close().await.unwrap();
+            assert!(!closer.is_open());
+            start.elapsed()
+        });
+        assert!(elapsed < Duration::from_millis(500), "{:?}", elapsed);
+        assert!(matches!(read, Err(Error::ReadError(_))));
+        assert!(reader.port.lock().unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_session_over_virtual_pty() {
+        let port = virtual_port().await;
+        let path = port.info().port.clone();
+        let (session, handle) = PortSession::new(port);
+        let mut events = handle.subscribe();
+
+        // A simulator on the device end
+        let simulator = std::thread::spawn(move || {
+            let mut device = OpenOptions::new().write(true).open(path).unwrap();
+            device.write_all(b"temp=21.5\nhum=40\n").unwrap();
+        });
+
+        let (_, lines) = tokio::join!(session.with_framer(LineFramer::default()).run(), async {
+            let mut lines = Vec::new();
+            while lines.len() < 2 {
+                if let SessionEvent::Message(message) = events.recv().await.unwrap() {
+                    lines.push(message.text().to_string());
+                }
+            }
+            handle.close().unwrap();
+            lines
+        });
+        simulator.join().unwrap();
+        assert_eq!(lines, vec!["temp=21.5", "hum=40"]);
     }
 }
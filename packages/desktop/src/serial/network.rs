@@ -0,0 +1,311 @@
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use project_core::{
+    serial::{rfc2217, PortConfig},
+    Error, Result,
+};
+
+/// How long to wait for the TCP connection to be accepted
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long to wait for the server to accept RFC 2217 COM port control
+const NEGOTIATION_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// TCP connection to a terminal server such as ser2net. Reads time out
+/// after `PortConfig::read_timeout`, like a local port.
+#[derive(Debug)]
+pub struct NetworkConnection {
+    /// Socket handle this connection reads from
+    stream: TcpStream,
+    /// Every write goes through this handle, shared by all clones, so a
+    /// Telnet reply sent by the reader never lands inside escaped data
+    writer: Arc<Mutex<TcpStream>>,
+    /// Present in RFC 2217 mode
+    telnet: Option<rfc2217::TelnetDecoder>,
+    /// Data received while negotiating, returned by the next reads
+    pending: Vec<u8>,
+}
+
+impl NetworkConnection {
+    /// Connect to `address` (`host:port`). With `rfc2217`, wait until the
+    /// server accepts COM port control, then push `config` to it.
+    pub fn connect(address: &str, rfc2217: bool, config: &PortConfig) -> Result<Self> {
+        let addresses = address
+            .to_socket_addrs()
+            .map_err(|e| Error::OpenError(format!("{}: {}", address, e)))?;
+        let mut last_error = None;
+        let stream = addresses
+            .into_iter()
+            .find_map(
+                |addr| match TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) {
+                    Ok(stream) => Some(stream),
+                    Err(e) => {
+                        last_error = Some(e);
+                        None
+                    }
+                },
+            )
+            .ok_or_else(|| {
+                let reason = last_error.map_or("no addresses".to_string(), |e| e.to_string());
+                Error::OpenError(format!("{}: {}", address, reason))
+            })?;
+        let open_error = |e: io::Error| Error::OpenError(e.to_string());
+        stream.set_nodelay(true).map_err(open_error)?;
+        stream
+            .set_read_timeout(Some(config.read_timeout))
+            .map_err(open_error)?;
+
+        let writer = stream.try_clone().map_err(open_error)?;
+        let mut connection = Self {
+            stream,
+            writer: Arc::new(Mutex::new(writer)),
+            telnet: rfc2217.then(rfc2217::TelnetDecoder::new),
+            pending: Vec::new(),
+        };
+        if rfc2217 {
+            connection.negotiate(address, config)?;
+        }
+        Ok(connection)
+    }
+
+    fn negotiate(&mut self, address: &str, config: &PortConfig) -> Result<()> {
+        let open_error = |e: io::Error| Error::OpenError(e.to_string());
+        self.send(&rfc2217::negotiate()).map_err(open_error)?;
+
+        let deadline = Instant::now() + NEGOTIATION_TIMEOUT;
+        loop {
+            match self.telnet.as_ref().and_then(|t| t.com_port()) {
+                // Strict servers ignore settings sent before they agree
+                Some(true) => return self.send(&rfc2217::settings(config)).map_err(open_error),
+                Some(false) => {
+                    return Err(Error::OpenError(format!(
+                        "{} refused RFC 2217 COM port control",
+                        address
+                    )))
+                }
+                None if Instant::now() >= deadline => {
+                    return Err(Error::OpenError(format!(
+                        "{} did not answer the RFC 2217 negotiation",
+                        address
+                    )))
+                }
+                None => {}
+            }
+            match self.receive() {
+                Ok(data) => self.pending.extend(data),
+                Err(e) if is_timeout(&e) => {}
+                Err(e) => return Err(open_error(e)),
+            }
+        }
+    }
+
+    /// Write `bytes` as they are, holding the shared write lock
+    fn send(&self, bytes: &[u8]) -> io::Result<()> {
+        self.writer.lock().unwrap().write_all(bytes)
+    }
+
+    /// A second handle to the same socket, for writing while another
+    /// thread reads. Its decoder only decides whether writes are escaped.
+    pub fn try_clone(&self) -> io::Result<Self> {
+        Ok(Self {
+            stream: self.stream.try_clone()?,
+            writer: self.writer.clone(),
+            telnet: self.telnet.as_ref().map(|_| rfc2217::TelnetDecoder::new()),
+            pending: Vec::new(),
+        })
+    }
+
+    /// Read one chunk from the socket and strip Telnet commands from it,
+    /// answering any the server expects a reply to
+    fn receive(&mut self) -> io::Result<Vec<u8>> {
+        let mut buf = [0u8; 1024];
+        let read = self.stream.read(&mut buf)?;
+        if read == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::ConnectionAborted,
+                "Connection closed by server",
+            ));
+        }
+        let Some(telnet) = self.telnet.as_mut() else {
+            return Ok(buf[..read].to_vec());
+        };
+        let data = telnet.decode(&buf[..read]);
+        let replies = telnet.take_replies();
+        if !replies.is_empty() {
+            self.send(&replies)?;
+        }
+        Ok(data)
+    }
+}
+
+impl Read for NetworkConnection {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // A chunk of only Telnet commands carries no data; keep reading
+        // until data arrives or the socket times out
+        while self.pending.is_empty() {
+            self.pending = self.receive()?;
+        }
+        let len = buf.len().min(self.pending.len());
+        buf[..len].copy_from_slice(&self.pending[..len]);
+        self.pending.drain(..len);
+        Ok(len)
+    }
+}
+
+impl Write for NetworkConnection {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.telnet {
+            Some(_) => self.send(&rfc2217::escape(buf)).map(|_| buf.len()),
+            None => self.writer.lock().unwrap().write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.lock().unwrap().flush()
+    }
+}
+
+/// Socket read timeouts surface as `WouldBlock` on Unix and `TimedOut` on
+/// Windows
+pub fn is_timeout(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+    use std::thread::{self, JoinHandle};
+
+    use project_core::serial::Parity;
+    use rfc2217::{TelnetDecoder, COM_PORT_OPTION, DO, DONT, IAC, SERVER_OFFSET};
+
+    use super::*;
+
+    /// COM port settings received, as `(command, value)`
+    type Settings = Vec<(u8, Vec<u8>)>;
+
+    #[derive(Clone, Copy)]
+    enum Server {
+        Raw,
+        Rfc2217,
+        Refuse,
+    }
+
+    /// A ser2net-like stand-in serving one client: it echoes data and, in
+    /// RFC 2217 mode, acknowledges each setting. Returns the settings it
+    /// received once the client disconnects.
+    fn stand_in(server: Server) -> (String, JoinHandle<Settings>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut telnet = TelnetDecoder::new().with_notifications();
+            let mut settings = Vec::new();
+            match server {
+                Server::Raw => {}
+                Server::Rfc2217 => {
+                    // Hold back DO until the client's opening request is in,
+                    // and check no setting came with it
+                    let mut buf = [0u8; 256];
+                    let read = stream.read(&mut buf).unwrap();
+                    telnet.decode(&buf[..read]);
+                    assert!(telnet.take_notifications().is_empty());
+                    stream.write_all(&[IAC, DO, COM_PORT_OPTION]).unwrap();
+                }
+                Server::Refuse => stream.write_all(&[IAC, DONT, COM_PORT_OPTION]).unwrap(),
+            }
+            let mut buf = [0u8; 256];
+            loop {
+                let read = match stream.read(&mut buf) {
+                    Ok(0) | Err(_) => return settings,
+                    Ok(read) => read,
+                };
+                if let Server::Raw = server {
+                    stream.write_all(&buf[..read]).unwrap();
+                    continue;
+                }
+                let data = telnet.decode(&buf[..read]);
+                for (command, value) in telnet.take_notifications() {
+                    let ack = rfc2217::subnegotiation(command + SERVER_OFFSET, &value);
+                    stream.write_all(&ack).unwrap();
+                    settings.push((command, value));
+                }
+                stream.write_all(&rfc2217::escape(&data)).unwrap();
+            }
+        });
+        (address, handle)
+    }
+
+    fn read_exact(connection: &mut NetworkConnection, len: usize) -> Vec<u8> {
+        let mut data = vec![0u8; len];
+        connection.read_exact(&mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn test_raw_connection() {
+        let (address, server) = stand_in(Server::Raw);
+        let mut connection =
+            NetworkConnection::connect(&address, false, &PortConfig::default()).unwrap();
+        connection.write_all(b"ping\xff").unwrap();
+        assert_eq!(read_exact(&mut connection, 5), b"ping\xff");
+        drop(connection);
+        assert!(server.join().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rfc2217_pushes_config() {
+        let (address, server) = stand_in(Server::Rfc2217);
+        let config = PortConfig::new(115200, 7, 2).with_parity(Parity::Even);
+        let mut connection = NetworkConnection::connect(&address, true, &config).unwrap();
+
+        // 0xFF survives the Telnet escaping both ways
+        connection.write_all(&[1, IAC, 2]).unwrap();
+        assert_eq!(read_exact(&mut connection, 3), vec![1, IAC, 2]);
+        // The server's acknowledgements were consumed, not kept
+        let telnet = connection.telnet.as_mut().unwrap();
+        assert!(telnet.take_notifications().is_empty());
+
+        drop(connection);
+        assert_eq!(
+            server.join().unwrap(),
+            vec![
+                (rfc2217::SET_BAUDRATE, 115200u32.to_be_bytes().to_vec()),
+                (rfc2217::SET_DATASIZE, vec![7]),
+                (rfc2217::SET_PARITY, vec![3]),
+                (rfc2217::SET_STOPSIZE, vec![2]),
+                (rfc2217::SET_CONTROL, vec![1]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rfc2217_refused() {
+        let (address, _server) = stand_in(Server::Refuse);
+        let result = NetworkConnection::connect(&address, true, &PortConfig::default());
+        assert!(matches!(result, Err(Error::OpenError(_))));
+    }
+
+    #[test]
+    fn test_read_timeout_and_server_close() {
+        let (address, server) = stand_in(Server::Raw);
+        let config = PortConfig::default().with_read_timeout(Duration::from_millis(20));
+        let mut connection = NetworkConnection::connect(&address, false, &config).unwrap();
+        let mut buf = [0u8; 8];
+        assert!(is_timeout(&connection.read(&mut buf).unwrap_err()));
+
+        // Closing our write side ends the stand-in, which closes its socket
+        connection
+            .stream
+            .shutdown(std::net::Shutdown::Write)
+            .unwrap();
+        server.join().unwrap();
+        let error = connection.read(&mut buf).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::ConnectionAborted);
+    }
+}
@@ -1,3 +1,7 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
 use project_core::data::Timestamp;
 use project_core::TimeSource;
 
@@ -13,4 +17,8 @@ impl TimeSource for SystemTimeSource {
             .as_millis() as u64;
         Timestamp(ms)
     }
+
+    fn sleep(duration: Duration) -> Pin<Box<dyn Future<Output = ()>>> {
+        Box::pin(tokio::time::sleep(duration))
+    }
 }
@@ -1,22 +1,40 @@
 use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::ops::ControlFlow;
 
-use dioxus::prelude::*;
+use dioxus::{
+    logger::tracing::{error, warn},
+    prelude::*,
+};
+use tokio::sync::broadcast::error::RecvError;
 use uuid::Uuid;
 
 use project_core::{
-    serial::{PortConfig, PortInfo, SerialPort},
+    data::PointBuffer,
+    framing::{ChecksumFramer, Framing},
+    modbus::{ModbusMaster, PollItem, Poller},
+    serial::{watch::PortEvent, PortConfig, PortInfo, SerialPort},
+    session::{PortSession, ReconnectPolicy, SessionEvent, SessionHandle},
     TimeSource,
 };
 
 use crate::hero::Hero;
+use crate::plot_context::PlotContext;
 use crate::serial_context::SerialContext;
 
-#[allow(non_snake_case)]
-#[component]
-pub fn App<S: SerialPort + 'static, T: TimeSource + 'static>() -> Element {
-    let _ = std::marker::PhantomData::<T>;
+#[derive(Props, Clone, PartialEq)]
+pub struct AppProps<S: SerialPort + 'static, T: TimeSource + 'static> {
+    /// Extra sidebar panels a platform adds; they can use `SerialContext`
+    children: Element,
+    #[props(default)]
+    platform: PhantomData<(S, T)>,
+}
 
+#[allow(non_snake_case)]
+pub fn App<S: SerialPort + 'static, T: TimeSource + 'static>(props: AppProps<S, T>) -> Element {
+    let children = props.children;
     let mut ports = use_signal(|| HashMap::<Uuid, S>::new());
+    let mut sessions = use_signal(HashMap::<Uuid, SessionHandle>::new);
     let port_list = use_memo(move || {
         let ports = ports.read();
         let mut list = HashMap::<Uuid, PortInfo>::new();
@@ -25,32 +43,181 @@ pub fn App<S: SerialPort + 'static, T: TimeSource + 'static>() -> Element {
         }
         list
     });
+    let framing = use_signal(Framing::default);
+    let checksum = use_signal(|| None);
+    let auto_reconnect = use_signal(|| true);
     let request_port = use_callback(move |(info, config): (PortInfo, PortConfig)| {
-        let mut res = use_signal(|| None);
-        use_future(move || {
-            let info = info.clone();
-            let config = config.clone();
-            async move {
-                let port = match S::request_port(info, config).await {
+        // Requesting may prompt the user, so the outcome is only known
+        // asynchronously; failures are logged from the spawned task.
+        spawn(async move {
+            let port = match S::request_port(info, config).await {
+                Ok(p) => p,
+                Err(e) => {
+                    error!("Failed to request port: {}", e);
+                    return;
+                }
+            };
+            let framer = match framing.peek().framer() {
+                Ok(framer) => framer,
+                Err(e) => {
+                    error!("Invalid framing: {}", e);
+                    return;
+                }
+            };
+            let id = Uuid::new_v4();
+            let (mut session, handle) = PortSession::new(port.clone());
+            if let Some(framer) = framer {
+                session = match *checksum.peek() {
+                    Some(checksum) => session.with_framer(ChecksumFramer::new(framer, checksum)),
+                    None => session.with_framer(framer),
+                };
+            }
+            if *auto_reconnect.peek() {
+                session = session.with_reconnect::<T>(ReconnectPolicy::default());
+            }
+            ports.write().insert(id, port);
+            sessions.write().insert(id, handle.clone());
+
+            // A reconnected device keeps its id; follow it to its new path
+            let mut events = handle.subscribe();
+            spawn(async move {
+                loop {
+                    match events.recv().await {
+                        Ok(SessionEvent::Reconnected(info)) => {
+                            if let Some(port) = ports.write().get_mut(&id) {
+                                *port = port.clone().with_port(info.port);
+                            }
+                        }
+                        Ok(SessionEvent::Closed) | Err(RecvError::Closed) => break,
+                        Ok(_) | Err(RecvError::Lagged(_)) => {}
+                    }
+                }
+            });
+
+            // Drive the session until the port is closed
+            session.run().await;
+
+            sessions.write().remove(&id);
+            ports.write().remove(&id);
+        });
+        Ok(())
+    });
+
+    let mut available = use_resource(move || async move { S::list_ports().await });
+    let available_ports = use_memo(move || match &*available.read() {
+        Some(Ok(list)) => list.clone(),
+        Some(Err(e)) => {
+            error!("Failed to list ports: {}", e);
+            Vec::new()
+        }
+        None => Vec::new(),
+    });
+    let refresh_ports = use_callback(move |_: ()| available.restart());
+
+    // Re-list whenever the platform reports a port coming or going, and
+    // note open ports whose device went away
+    let mut unplugged = use_signal(Vec::<PortInfo>::new);
+    use_future(move || async move {
+        let result = S::watch_ports(&mut |event| {
+            if let PortEvent::Removed(info) = &event {
+                // `PortInfo::id` tells apart ports that share a name
+                if ports.peek().values().any(|port| port.info() == info) {
+                    warn!("{} was unplugged while open", info.port);
+                    unplugged.write().push(info.clone());
+                }
+            }
+            available.restart();
+            ControlFlow::Continue(())
+        })
+        .await;
+        if let Err(e) = result {
+            error!("Stopped watching ports: {}", e);
+        }
+    });
+    let now = use_callback(move |_: ()| T::now_millis());
+
+    let mut points = use_signal(PointBuffer::default);
+    // Bumped to stop the running poll; each poll task remembers its own
+    let mut modbus_generation = use_signal(|| 0u64);
+    let mut modbus_running = use_signal(|| false);
+    let start_modbus = use_callback(
+        move |(info, config, items): (PortInfo, PortConfig, Vec<PollItem>)| {
+            let mut poller = Poller::new(items)?;
+            let generation = *modbus_generation.peek() + 1;
+            modbus_generation.set(generation);
+            modbus_running.set(true);
+            let is_current = move || *modbus_generation.peek() == generation;
+
+            spawn(async move {
+                let mut port = match S::request_port(info, config).await {
                     Ok(p) => p,
                     Err(e) => {
-                        res.set(Some(Err(e)));
+                        error!("Failed to request Modbus port: {}", e);
+                        if is_current() {
+                            modbus_running.set(false);
+                        }
                         return;
                     }
                 };
-                let id = Uuid::new_v4();
-                ports.write().insert(id, port);
-                res.set(Some(Ok(())))
-            }
-        });
-        // In order to resolve a lifetime issue, you need to clone result here.
-        let res = res.read().clone().transpose().map(|_| ());
-        res
+                if !port.is_open() {
+                    if let Err(e) = port.open().await {
+                        error!("Failed to open Modbus port: {}", e);
+                        if is_current() {
+                            modbus_running.set(false);
+                        }
+                        return;
+                    }
+                }
+
+                let mut master = ModbusMaster::<S, T>::new(port);
+                poller
+                    .run(&mut master, |result| {
+                        if !is_current() {
+                            return ControlFlow::Break(());
+                        }
+                        match result {
+                            Ok(point) => points.write().push(point),
+                            Err(e) => warn!("Modbus poll failed: {}", e),
+                        }
+                        ControlFlow::Continue(())
+                    })
+                    .await;
+
+                if let Err(e) = master.port_mut().close().await {
+                    error!("Failed to close Modbus port: {}", e);
+                }
+                if is_current() {
+                    modbus_running.set(false);
+                }
+            });
+            Ok(())
+        },
+    );
+    let stop_modbus = use_callback(move |_: ()| {
+        modbus_generation += 1;
+        modbus_running.set(false);
     });
 
     use_context_provider(|| SerialContext {
         request_port,
+        network_ports: S::NETWORK_PORTS,
         port_list: port_list.into(),
+        available_ports: available_ports.into(),
+        refresh_ports,
+        unplugged,
+        sessions: sessions.into(),
+        now,
+        framing,
+        checksum,
+        auto_reconnect,
+        start_modbus,
+        stop_modbus,
+        modbus_running: modbus_running.into(),
+    });
+
+    use_context_provider(|| PlotContext {
+        parsers: Signal::new(PlotContext::default_parsers()),
+        points,
     });
 
     rsx! {
@@ -62,6 +229,6 @@ pub fn App<S: SerialPort + 'static, T: TimeSource + 'static>() -> Element {
 
         Hero {}
 
-        crate::layout::Layout {}
+        crate::layout::Layout { {children} }
     }
 }
@@ -1,13 +1,17 @@
 mod connection_bar;
 mod graph;
+mod modbus_panel;
 mod notifications;
 mod port_list;
 mod request_port;
 mod settings_panel;
+mod terminal;
 
 pub use connection_bar::ConnectionBar;
 pub use graph::Graph;
+pub use modbus_panel::ModbusPanel;
 pub use notifications::Notifications;
 pub use port_list::PortList;
 pub use request_port::RequestPort;
 pub use settings_panel::SettingsPanel;
+pub use terminal::Terminal;
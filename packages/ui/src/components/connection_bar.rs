@@ -1,12 +1,47 @@
-use dioxus::prelude::*;
+use dioxus::{logger::tracing::error, prelude::*};
+
+use crate::serial_context::SerialContext;
 
 #[allow(non_snake_case)]
 #[component]
 pub fn ConnectionBar() -> Element {
+    let serial_context = use_context::<SerialContext>();
+    let port_list = serial_context.port_list;
+    let sessions = serial_context.sessions;
+
     rsx!(
         div { class: "connection-bar",
             h4 { "Connection" }
-            p { "(connection controls skeleton)" }
+            if sessions.read().is_empty() {
+                p { "No open ports" }
+            }
+            ul {
+                {
+                    sessions.read().iter().map(|(id, handle)| {
+                        let id_str = id.to_string();
+                        let name = port_list
+                            .read()
+                            .get(id)
+                            .map(|info| info.port.clone())
+                            .unwrap_or_default();
+                        let handle = handle.clone();
+                        rsx!(
+                            li {
+                                key: "{id_str}",
+                                "{name} "
+                                button {
+                                    onclick: move |_| {
+                                        handle.close().unwrap_or_else(|e| {
+                                            error!("{}", e);
+                                        });
+                                    },
+                                    "Close"
+                                }
+                            }
+                        )
+                    })
+                }
+            }
         }
     )
 }
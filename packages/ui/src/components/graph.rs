@@ -1,7 +1,234 @@
 use dioxus::prelude::*;
 
+use project_core::{
+    data::{Direction, PointBuffer},
+    session::SessionEvent,
+    Parse,
+};
+
+use crate::plot_context::PlotContext;
+use crate::serial_context::use_session_events;
+
+const WIDTH: f64 = 600.0;
+const HEIGHT: f64 = 240.0;
+const MARGIN_LEFT: f64 = 56.0;
+const MARGIN_RIGHT: f64 = 8.0;
+const MARGIN_TOP: f64 = 8.0;
+const MARGIN_BOTTOM: f64 = 20.0;
+const Y_TICKS: usize = 5;
+
+/// Selectable time windows in seconds
+const WINDOWS: [u64; 4] = [5, 10, 30, 60];
+
+const PALETTE: [&str; 8] = [
+    "#60a5fa", "#f87171", "#34d399", "#fbbf24", "#a78bfa", "#f472b6", "#22d3ee", "#a3e635",
+];
+
+/// One polyline of the chart
+struct Series {
+    label: String,
+    color: &'static str,
+    points: String,
+    last: f64,
+}
+
 #[allow(non_snake_case)]
 #[component]
 pub fn Graph() -> Element {
-    rsx!( div { class: "graph-placeholder", h4 { "Graph Placeholder" } p { "(replace with real graph later)" } } )
+    let plot = use_context::<PlotContext>();
+    let parsers = plot.parsers;
+    let mut points = plot.points;
+
+    let mut window_secs = use_signal(|| 10u64);
+    // While paused the chart shows this snapshot; parsing continues
+    let mut snapshot = use_signal(|| None::<PointBuffer>);
+
+    use_session_events(move |_, event| {
+        let SessionEvent::Message(message) = event else {
+            return;
+        };
+        if message.direction() != Direction::In {
+            return;
+        }
+        // Lines that don't match the pattern are simply not plotted
+        if let Ok(parsed) = parsers.read().parse_message(&message) {
+            let mut points = points.write();
+            for point in parsed {
+                points.push(point);
+            }
+        }
+    });
+
+    let paused = snapshot.read().is_some();
+    let (series, (y_min, y_max)) = match &*snapshot.read() {
+        Some(buffer) => build_series(buffer, window_secs() * 1000),
+        None => build_series(&points.read(), window_secs() * 1000),
+    };
+
+    let plot_bottom = HEIGHT - MARGIN_BOTTOM;
+    let y_ticks = (0..Y_TICKS).map(|i| {
+        let fraction = i as f64 / (Y_TICKS - 1) as f64;
+        let value = y_min + (y_max - y_min) * fraction;
+        let y = plot_bottom - (plot_bottom - MARGIN_TOP) * fraction;
+        (format_value(value), y)
+    });
+
+    rsx!(
+        div { class: "graph",
+            div { class: "graph-toolbar",
+                h3 { "Graph" }
+                select {
+                    onchange: move |event| {
+                        if let Ok(secs) = event.value().parse() {
+                            window_secs.set(secs);
+                        }
+                    },
+                    for secs in WINDOWS {
+                        option { value: "{secs}", selected: secs == window_secs(), "{secs} s" }
+                    }
+                }
+                button {
+                    onclick: move |_| {
+                        if paused {
+                            snapshot.set(None);
+                        } else {
+                            snapshot.set(Some(points.read().clone()));
+                        }
+                    },
+                    if paused { "Resume" } else { "Pause" }
+                }
+                button { onclick: move |_| points.write().clear(), "Clear" }
+            }
+            svg {
+                class: "graph-canvas",
+                view_box: "0 0 {WIDTH} {HEIGHT}",
+                width: "100%",
+                line {
+                    x1: "{MARGIN_LEFT}",
+                    y1: "{MARGIN_TOP}",
+                    x2: "{MARGIN_LEFT}",
+                    y2: "{plot_bottom}",
+                    stroke: "#4b5563",
+                }
+                line {
+                    x1: "{MARGIN_LEFT}",
+                    y1: "{plot_bottom}",
+                    x2: "{WIDTH - MARGIN_RIGHT}",
+                    y2: "{plot_bottom}",
+                    stroke: "#4b5563",
+                }
+                for (label, y) in y_ticks {
+                    text {
+                        x: "{MARGIN_LEFT - 4.0}",
+                        y: "{y}",
+                        fill: "#9ca3af",
+                        font_size: "10",
+                        text_anchor: "end",
+                        dominant_baseline: "middle",
+                        "{label}"
+                    }
+                }
+                text {
+                    x: "{MARGIN_LEFT}",
+                    y: "{HEIGHT - 4.0}",
+                    fill: "#9ca3af",
+                    font_size: "10",
+                    "-{window_secs} s"
+                }
+                text {
+                    x: "{WIDTH - MARGIN_RIGHT}",
+                    y: "{HEIGHT - 4.0}",
+                    fill: "#9ca3af",
+                    font_size: "10",
+                    text_anchor: "end",
+                    "latest"
+                }
+                for s in series.iter() {
+                    polyline {
+                        key: "{s.label}",
+                        points: "{s.points}",
+                        fill: "none",
+                        stroke: s.color,
+                        stroke_width: "1.5",
+                    }
+                }
+            }
+            ul { class: "graph-legend",
+                for s in series.iter() {
+                    li { key: "{s.label}",
+                        span { style: "color: {s.color}", "■ " }
+                        "{s.label}: {format_value(s.last)}"
+                    }
+                }
+            }
+        }
+    )
+}
+
+/// Project the points of the last `window_ms` onto the chart, one series per
+/// label, and return them with the (padded) Y range used.
+fn build_series(buffer: &PointBuffer, window_ms: u64) -> (Vec<Series>, (f64, f64)) {
+    let Some(end) = buffer.last().map(|p| p.timestamp().as_millis()) else {
+        return (Vec::new(), (0.0, 1.0));
+    };
+    let start = end.saturating_sub(window_ms);
+    let visible = || {
+        buffer
+            .iter()
+            .filter(move |p| p.timestamp().as_millis() >= start && p.value().is_finite())
+    };
+
+    let (mut y_min, mut y_max) = visible().fold((f64::INFINITY, f64::NEG_INFINITY), |acc, p| {
+        (acc.0.min(p.value()), acc.1.max(p.value()))
+    });
+    if !y_min.is_finite() {
+        return (Vec::new(), (0.0, 1.0));
+    }
+    if y_min == y_max {
+        y_min -= 1.0;
+        y_max += 1.0;
+    }
+    let padding = (y_max - y_min) * 0.05;
+    let (y_min, y_max) = (y_min - padding, y_max + padding);
+
+    let plot_width = WIDTH - MARGIN_LEFT - MARGIN_RIGHT;
+    let plot_height = HEIGHT - MARGIN_TOP - MARGIN_BOTTOM;
+    let series = buffer
+        .labels()
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, label)| {
+            let mut last = None;
+            let points = visible()
+                .filter(|p| p.label() == label)
+                .map(|p| {
+                    last = Some(p.value());
+                    let x = MARGIN_LEFT
+                        + plot_width * (p.timestamp().as_millis() - start) as f64
+                            / window_ms.max(1) as f64;
+                    let y = MARGIN_TOP + plot_height * (y_max - p.value()) / (y_max - y_min);
+                    format!("{:.1},{:.1}", x, y)
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            Some(Series {
+                label: label.unwrap_or("value").to_string(),
+                color: PALETTE[i % PALETTE.len()],
+                points,
+                last: last?,
+            })
+        })
+        .collect();
+
+    (series, (y_min, y_max))
+}
+
+/// Compact number formatting for axis ticks and the legend
+fn format_value(value: f64) -> String {
+    let magnitude = value.abs();
+    if magnitude != 0.0 && !(0.01..100_000.0).contains(&magnitude) {
+        format!("{:.2e}", value)
+    } else {
+        format!("{:.2}", value)
+    }
 }
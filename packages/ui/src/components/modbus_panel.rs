@@ -0,0 +1,88 @@
+use dioxus::prelude::*;
+
+use project_core::{
+    modbus::PollItem,
+    serial::{PortConfig, PortInfo},
+    Result as CoreResult,
+};
+
+use crate::serial_context::SerialContext;
+
+/// One `PollItem` per non-empty line
+fn parse_items(text: &str) -> CoreResult<Vec<PollItem>> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+#[allow(non_snake_case)]
+#[component]
+pub fn ModbusPanel() -> Element {
+    let serial_context = use_context::<SerialContext>();
+    let available_ports = serial_context.available_ports;
+    let start_modbus = serial_context.start_modbus;
+    let stop_modbus = serial_context.stop_modbus;
+    let running = serial_context.modbus_running;
+
+    let mut port = use_signal(String::new);
+    let mut baud_rate = use_signal(|| "9600".to_string());
+    let mut items = use_signal(|| "temp 1 holding 0 i16 0.1 1000".to_string());
+    let mut config_error = use_signal(|| None::<String>);
+
+    let start = move |_| {
+        let result = baud_rate()
+            .trim()
+            .parse::<u32>()
+            .map_err(|_| format!("Invalid baud rate '{}'", baud_rate()))
+            .and_then(|baud| {
+                let items = parse_items(&items()).map_err(|e| e.to_string())?;
+                // An empty selection lets the platform prompt for a port
+                let info = available_ports
+                    .read()
+                    .iter()
+                    .find(|info| info.port == port())
+                    .cloned()
+                    .unwrap_or_default();
+                start_modbus
+                    .call((info, PortConfig::new(baud, 8, 1), items))
+                    .map_err(|e| e.to_string())
+            });
+        config_error.set(result.err());
+    };
+
+    rsx!(
+        div { class: "modbus-panel",
+            h4 { "Modbus RTU" }
+            label { "Port"
+                select {
+                    value: "{port}",
+                    onchange: move |e| port.set(e.value()),
+                    option { value: "", "Choose when starting" }
+                    for info in available_ports.read().iter().cloned().collect::<Vec<PortInfo>>() {
+                        option { value: "{info.port}", "{info.port}" }
+                    }
+                }
+            }
+            label { "Baud"
+                input { value: "{baud_rate}", oninput: move |e| baud_rate.set(e.value()) }
+            }
+            label { "Poll (label unit table address [format] [scale] [interval_ms])"
+                textarea {
+                    rows: 4,
+                    value: "{items}",
+                    oninput: move |e| items.set(e.value()),
+                }
+            }
+            if running() {
+                button { onclick: move |_| stop_modbus.call(()), "Stop" }
+            } else {
+                button { onclick: start, "Start" }
+            }
+            if let Some(error) = config_error() {
+                p { class: "settings-error", "{error}" }
+            }
+        }
+    )
+}
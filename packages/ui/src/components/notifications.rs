@@ -1,11 +1,94 @@
+use std::collections::HashMap;
+
 use dioxus::prelude::*;
+use uuid::Uuid;
+
+use project_core::{session::SessionEvent, Error};
+
+use crate::serial_context::{use_session_events, SerialContext};
+
+/// Errors seen on one port since it was opened or last reset
+#[derive(Clone, Default, PartialEq)]
+struct PortErrors {
+    /// Port name, kept after the port is closed
+    name: String,
+    /// Frames rejected by framing or checksum verification
+    bad_frames: usize,
+    /// Read, write and other errors
+    other: usize,
+    last: Option<String>,
+}
 
 #[allow(non_snake_case)]
 #[component]
 pub fn Notifications() -> Element {
+    let serial_context = use_context::<SerialContext>();
+    let port_list = serial_context.port_list;
+    let mut unplugged = serial_context.unplugged;
+    let mut errors = use_signal(HashMap::<Uuid, PortErrors>::new);
+
+    use_session_events(move |id, event| {
+        let SessionEvent::Error(error) = event else {
+            return;
+        };
+        let mut errors = errors.write();
+        let entry = errors.entry(id).or_insert_with(|| PortErrors {
+            name: port_list
+                .peek()
+                .get(&id)
+                .map(|info| info.port.clone())
+                .unwrap_or_default(),
+            ..Default::default()
+        });
+        match error {
+            Error::ParseError(_) => entry.bad_frames += 1,
+            _ => entry.other += 1,
+        }
+        entry.last = Some(error.to_string());
+    });
+
+    let mut entries: Vec<(Uuid, PortErrors)> = errors
+        .read()
+        .iter()
+        .map(|(id, e)| (*id, e.clone()))
+        .collect();
+    entries.sort_by(|a, b| a.1.name.cmp(&b.1.name));
+
     rsx!(
         div { class: "notifications",
-            p { "(notifications skeleton)" }
+            h4 { "Notifications" }
+            if entries.is_empty() && unplugged.read().is_empty() {
+                p { "No errors" }
+            }
+            ul {
+                for (index, info) in unplugged.read().iter().enumerate() {
+                    li { key: "unplugged-{index}-{info.port}", class: "notification-unplugged",
+                        "{info.port} was unplugged while open "
+                        button {
+                            onclick: move |_| {
+                                unplugged.write().remove(index);
+                            },
+                            "Dismiss"
+                        }
+                    }
+                }
+            }
+            ul {
+                for (id, port_errors) in entries {
+                    li { key: "{id}",
+                        "{port_errors.name}: {port_errors.bad_frames} bad frames, {port_errors.other} errors "
+                        button {
+                            onclick: move |_| {
+                                errors.write().remove(&id);
+                            },
+                            "Reset"
+                        }
+                        if let Some(last) = &port_errors.last {
+                            p { class: "notification-last", "{last}" }
+                        }
+                    }
+                }
+            }
         }
     )
 }
@@ -1,10 +1,50 @@
-use dioxus::prelude::*;
+use dioxus::{logger::tracing::error, prelude::*};
+
+use project_core::serial::{PortConfig, PortInfo, PortType};
+
+use crate::serial_context::SerialContext;
 
 #[allow(non_snake_case)]
 #[component]
 pub fn PortList() -> Element {
-    let serial_context = use_context::<crate::serial_context::SerialContext>();
+    let serial_context = use_context::<SerialContext>();
     let port_list = serial_context.port_list;
+    let available_ports = serial_context.available_ports;
+    let request_port = serial_context.request_port;
+    let refresh_ports = serial_context.refresh_ports;
+    let mut address = use_signal(String::new);
+    let mut baud_rate = use_signal(|| PortConfig::default().baud_rate.to_string());
+    let mut rfc2217 = use_signal(|| false);
+
+    let connect = move |_| {
+        let Ok(baud_rate) = baud_rate.read().trim().parse() else {
+            error!("Invalid baud rate '{}'", baud_rate.read());
+            return;
+        };
+        let info = PortInfo::new(
+            address.read().trim().to_string(),
+            PortType::Network { rfc2217: rfc2217() },
+            None,
+        );
+        let config = PortConfig {
+            baud_rate,
+            ..PortConfig::default()
+        };
+        request_port
+            .call((info, config))
+            .unwrap_or_else(|e| error!("{}", e));
+    };
+
+    // Ports that are present on the system but not yet held by the app
+    let unopened = use_memo(move || {
+        let opened = port_list.read();
+        available_ports
+            .read()
+            .iter()
+            .filter(|info| !opened.values().any(|o| o == *info))
+            .cloned()
+            .collect::<Vec<_>>()
+    });
 
     rsx!(
         div { class: "port-list",
@@ -14,48 +54,110 @@ pub fn PortList() -> Element {
                     // port_list is a HashMap<Uuid, PortInfo>; iterate over (id, info)
                     port_list.read().iter().map(|(id, port_info)| {
                         let id_str = id.to_string();
-                        let name = port_info.port.clone();
-                        match &port_info.port_type {
-                            project_core::serial::PortType::WebSerial {
-                                vendor_id,
-                                product_id,
-                                bluetooth_service_class_id: _
-                            } => {
-                                let vid_str = match vendor_id {
-                                    Some(id) => format!("{:04X}", id),
-                                    None => "None".to_string(),
-                                };
-                                let pid_str = match product_id {
-                                    Some(id) => format!("{:04X}", id),
-                                    None => "None".to_string(),
-                                };
-                                let description = port_info.description
-                                    .clone()
-                                    .map(|d| format!(" - {}", d))
-                                    .unwrap_or_default();
-                                rsx!(
-                                    li {
-                                        key: "{id_str}",
-                                        "{name} ({pid_str}:{vid_str}){description}"
-                                    }
-                                )
+                        let label = port_label(port_info);
+                        rsx!(
+                            li {
+                                key: "{id_str}",
+                                "{label}"
                             }
-                            _ => {
-                                let description = port_info.description
-                                    .clone()
-                                    .map(|d| format!(" - {}", d))
-                                    .unwrap_or_default();
-                                rsx!(
-                                    li {
-                                        key: "{id_str}",
-                                        "{name}{description}"
+                        )
+                    })
+                }
+            }
+            div { class: "available-ports",
+                h4 { "Available" }
+                button { onclick: move |_| refresh_ports.call(()), "Refresh" }
+                ul {
+                    {
+                        unopened.read().iter().map(|port_info| {
+                            // Web Serial ports share a name; the id tells them apart
+                            let key = match port_info.id {
+                                Some(id) => format!("{}#{}", port_info.port, id),
+                                None => port_info.port.clone(),
+                            };
+                            let label = port_label(port_info);
+                            let info = port_info.clone();
+                            rsx!(
+                                li {
+                                    key: "{key}",
+                                    "{label} "
+                                    button {
+                                        onclick: move |_| {
+                                            request_port
+                                                .call((info.clone(), PortConfig::default()))
+                                                .unwrap_or_else(|e| {
+                                                    error!("{}", e);
+                                                });
+                                        },
+                                        "Open"
                                     }
-                                )
-                            }
+                                }
+                            )
+                        })
+                    }
+                }
+            }
+            if serial_context.network_ports {
+                div { class: "network-port",
+                    h4 { "Network" }
+                    input {
+                        placeholder: "host:port",
+                        value: "{address}",
+                        oninput: move |e| address.set(e.value()),
+                    }
+                    label { "Baud"
+                        input { value: "{baud_rate}", oninput: move |e| baud_rate.set(e.value()) }
+                    }
+                    label {
+                        input {
+                            r#type: "checkbox",
+                            checked: rfc2217(),
+                            onchange: move |e| rfc2217.set(e.checked()),
                         }
-                    })
+                        " RFC 2217"
+                    }
+                    button {
+                        disabled: address.read().trim().is_empty(),
+                        onclick: connect,
+                        "Connect"
+                    }
                 }
             }
         }
     )
 }
+
+/// Human-readable one-line summary of a port
+fn port_label(port_info: &PortInfo) -> String {
+    let name = &port_info.port;
+    let description = port_info
+        .description
+        .clone()
+        .map(|d| format!(" - {}", d))
+        .unwrap_or_default();
+    let id_str = |id: &Option<u16>| match id {
+        Some(id) => format!("{:04X}", id),
+        None => "None".to_string(),
+    };
+    match &port_info.port_type {
+        PortType::WebSerial {
+            vendor_id,
+            product_id,
+            bluetooth_service_class_id: _,
+        }
+        | PortType::Usb {
+            vendor_id,
+            product_id,
+            ..
+        } => format!(
+            "{name} ({}:{}){description}",
+            id_str(vendor_id),
+            id_str(product_id)
+        ),
+        PortType::Network { rfc2217 } => format!(
+            "{name} ({}){description}",
+            if *rfc2217 { "RFC 2217" } else { "TCP" }
+        ),
+        _ => format!("{name}{description}"),
+    }
+}
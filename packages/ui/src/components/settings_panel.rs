@@ -1,12 +1,251 @@
 use dioxus::prelude::*;
 
+use project_core::{
+    framing::{Checksum, Delimiter, Framing},
+    MatchMode, ParserConfig,
+};
+
+use crate::plot_context::PlotContext;
+use crate::serial_context::SerialContext;
+
+/// Framing choices offered in the settings, keyed by `<select>` value
+fn framing_options() -> [(&'static str, &'static str, Framing); 5] {
+    [
+        ("lf", "Lines (LF)", Framing::Line(Delimiter::Lf)),
+        ("crlf", "Lines (CRLF)", Framing::Line(Delimiter::CrLf)),
+        ("cobs", "COBS", Framing::Cobs),
+        ("slip", "SLIP", Framing::Slip),
+        ("raw", "Raw chunks", Framing::Raw),
+    ]
+}
+
+/// Checksum choices offered in the settings, keyed by `<select>` value
+fn checksum_options() -> [(&'static str, &'static str, Option<Checksum>); 6] {
+    [
+        ("none", "None", None),
+        ("crc8", "CRC-8", Some(Checksum::Crc8)),
+        ("crc16-ccitt", "CRC-16 (CCITT)", Some(Checksum::Crc16Ccitt)),
+        (
+            "crc16-modbus",
+            "CRC-16 (Modbus)",
+            Some(Checksum::Crc16Modbus),
+        ),
+        ("crc32", "CRC-32", Some(Checksum::Crc32)),
+        ("nmea", "NMEA XOR", Some(Checksum::NmeaXor)),
+    ]
+}
+
+/// Comma-separated names, blanks dropped
+fn split_paths(text: &str) -> Vec<String> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|path| !path.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
 #[allow(non_snake_case)]
 #[component]
 pub fn SettingsPanel() -> Element {
+    let plot = use_context::<PlotContext>();
+    let mut parsers = plot.parsers;
+    let serial_context = use_context::<SerialContext>();
+    let mut framing = serial_context.framing;
+    let mut checksum = serial_context.checksum;
+    let mut auto_reconnect = serial_context.auto_reconnect;
+    let framing_key = framing_options()
+        .into_iter()
+        .find(|(_, _, f)| *f == *framing.read())
+        .map_or("raw", |(key, _, _)| key);
+    let checksum_key = checksum_options()
+        .into_iter()
+        .find(|(_, _, c)| *c == checksum())
+        .map_or("none", |(key, _, _)| key);
+
+    let mut kind = use_signal(|| "regex".to_string());
+    let mut pattern = use_signal(String::new);
+    let mut label_expr = use_signal(String::new);
+    let mut value_expr = use_signal(|| "$1".to_string());
+    let mut all_matches = use_signal(|| false);
+    let mut include = use_signal(String::new);
+    let mut exclude = use_signal(String::new);
+    let mut delimiter = use_signal(|| ",".to_string());
+    let mut columns = use_signal(String::new);
+    let mut config_error = use_signal(|| None::<String>);
+
+    let add = move |_| {
+        let config = match kind().as_str() {
+            "json" => ParserConfig::Json {
+                include: split_paths(&include()),
+                exclude: split_paths(&exclude()),
+            },
+            "nmea" => ParserConfig::Nmea {
+                require_checksum: true,
+            },
+            "delimited" => {
+                let delimiter = match delimiter().as_str() {
+                    "\\t" => '\t',
+                    text => match text.chars().next() {
+                        Some(c) => c,
+                        None => {
+                            config_error.set(Some("Delimiter is empty".to_string()));
+                            return;
+                        }
+                    },
+                };
+                ParserConfig::Delimited {
+                    delimiter,
+                    columns: split_paths(&columns()),
+                }
+            }
+            _ => ParserConfig::Regex {
+                pattern: pattern(),
+                label: label_expr(),
+                value: value_expr(),
+                match_mode: if all_matches() {
+                    MatchMode::All
+                } else {
+                    MatchMode::First
+                },
+                fields: Vec::new(),
+            },
+        };
+        match config.build() {
+            Ok(parser) => {
+                parsers.write().push(parser);
+                config_error.set(None);
+            }
+            Err(e) => config_error.set(Some(e.to_string())),
+        }
+    };
+
+    let descriptions: Vec<String> = parsers.read().iter().map(|p| p.description()).collect();
+
     rsx!(
         div { class: "settings-panel",
-            h4 { "Settings" }
-            p { "(settings skeleton)" }
+            h4 { "Framing" }
+            label { "Frames"
+                select {
+                    value: framing_key,
+                    onchange: move |e| {
+                        if let Some((_, _, f)) = framing_options()
+                            .into_iter()
+                            .find(|(key, _, _)| *key == e.value())
+                        {
+                            framing.set(f);
+                        }
+                    },
+                    for (key, name, _) in framing_options() {
+                        option { value: key, selected: key == framing_key, "{name}" }
+                    }
+                }
+            }
+            label { "Checksum"
+                select {
+                    value: checksum_key,
+                    onchange: move |e| {
+                        if let Some((_, _, c)) = checksum_options()
+                            .into_iter()
+                            .find(|(key, _, _)| *key == e.value())
+                        {
+                            checksum.set(c);
+                        }
+                    },
+                    for (key, name, _) in checksum_options() {
+                        option { value: key, selected: key == checksum_key, "{name}" }
+                    }
+                }
+            }
+            label {
+                input {
+                    r#type: "checkbox",
+                    checked: auto_reconnect(),
+                    onchange: move |e| auto_reconnect.set(e.checked()),
+                }
+                " Reconnect when the device disappears"
+            }
+            p { "Applies to ports opened afterwards" }
+            h4 { "Parsers" }
+            if descriptions.is_empty() {
+                p { "No parsers; nothing is plotted" }
+            }
+            ul {
+                for (index, description) in descriptions.into_iter().enumerate() {
+                    li { key: "{index}-{description}",
+                        span { "{description}" }
+                        button {
+                            onclick: move |_| {
+                                parsers.write().remove(index);
+                            },
+                            "Remove"
+                        }
+                    }
+                }
+            }
+            label { "Kind"
+                select {
+                    value: "{kind}",
+                    onchange: move |e| kind.set(e.value()),
+                    option { value: "regex", "Regex" }
+                    option { value: "json", "JSON" }
+                    option { value: "delimited", "Delimited" }
+                    option { value: "nmea", "NMEA 0183" }
+                }
+            }
+            if kind() == "json" {
+                label { "Include"
+                    input {
+                        placeholder: "imu.accel, temp",
+                        value: "{include}",
+                        oninput: move |e| include.set(e.value()),
+                    }
+                }
+                label { "Exclude"
+                    input {
+                        value: "{exclude}",
+                        oninput: move |e| exclude.set(e.value()),
+                    }
+                }
+            } else if kind() == "nmea" {
+                p { "GGA, RMC, VTG, GSA and GSV sentences" }
+            } else if kind() == "delimited" {
+                label { "Delimiter"
+                    input {
+                        placeholder: ", or \\t",
+                        value: "{delimiter}",
+                        oninput: move |e| delimiter.set(e.value()),
+                    }
+                }
+                label { "Columns"
+                    input {
+                        placeholder: "from header line",
+                        value: "{columns}",
+                        oninput: move |e| columns.set(e.value()),
+                    }
+                }
+            } else {
+                label { "Pattern"
+                    input { value: "{pattern}", oninput: move |e| pattern.set(e.value()) }
+                }
+                label { "Label"
+                    input { value: "{label_expr}", oninput: move |e| label_expr.set(e.value()) }
+                }
+                label { "Value"
+                    input { value: "{value_expr}", oninput: move |e| value_expr.set(e.value()) }
+                }
+                label {
+                    input {
+                        r#type: "checkbox",
+                        checked: all_matches(),
+                        onchange: move |e| all_matches.set(e.checked()),
+                    }
+                    "All matches per line"
+                }
+            }
+            button { onclick: add, "Add parser" }
+            if let Some(error) = config_error() {
+                p { class: "settings-error", "{error}" }
+            }
         }
     )
 }
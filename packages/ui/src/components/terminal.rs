@@ -0,0 +1,204 @@
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use dioxus::html::geometry::PixelsVector2D;
+use dioxus::html::ScrollBehavior;
+use dioxus::{logger::tracing::error, prelude::*};
+use uuid::Uuid;
+
+use project_core::{
+    data::{Direction, Message, Timestamp},
+    session::SessionEvent,
+};
+
+use crate::serial_context::{use_session_events, SerialContext};
+
+const TERMINAL_CSS: Asset = asset!("/assets/styling/terminal.css");
+
+/// Maximum number of entries kept; older ones are dropped first
+const MAX_HISTORY: usize = 5000;
+
+/// Distance from the bottom (px) still treated as "at the bottom"
+const FOLLOW_THRESHOLD: f64 = 8.0;
+
+#[allow(non_snake_case)]
+#[component]
+pub fn Terminal() -> Element {
+    let serial_context = use_context::<SerialContext>();
+    let sessions = serial_context.sessions;
+    let port_list = serial_context.port_list;
+    let now = serial_context.now;
+    let framing = serial_context.framing;
+    let checksum = serial_context.checksum;
+
+    // Entries are keyed by a running sequence number so keys stay stable
+    // as old entries are dropped
+    let mut history = use_signal(VecDeque::<(u64, Uuid, SessionEvent)>::new);
+    let mut next_seq = use_signal(|| 0u64);
+    // Auto-scroll while true; cleared when the user scrolls up
+    let mut follow = use_signal(|| true);
+    let mut log_element = use_signal(|| None::<Rc<MountedData>>);
+    let mut input = use_signal(String::new);
+    let mut target = use_signal(|| None::<Uuid>);
+
+    use_session_events(move |id, event| {
+        let seq = next_seq();
+        next_seq.set(seq + 1);
+        let mut history = history.write();
+        history.push_back((seq, id, event));
+        while history.len() > MAX_HISTORY {
+            history.pop_front();
+        }
+    });
+
+    // Keep the newest entry in view while following
+    use_effect(move || {
+        let _ = history.read().len();
+        if !follow() {
+            return;
+        }
+        if let Some(element) = log_element() {
+            spawn(async move {
+                if let Ok(size) = element.get_scroll_size().await {
+                    let _ = element
+                        .scroll(
+                            PixelsVector2D::new(0.0, size.height),
+                            ScrollBehavior::Instant,
+                        )
+                        .await;
+                }
+            });
+        }
+    });
+
+    let onscroll = move |event: ScrollEvent| {
+        let bottom = event.scroll_top() + event.client_height() as f64;
+        follow.set(bottom + FOLLOW_THRESHOLD >= event.scroll_height() as f64);
+    };
+
+    // Forget the chosen port once it is closed, rather than sending to
+    // whichever port is left
+    use_effect(move || {
+        let sessions = sessions.read();
+        if target.peek().is_some_and(|id| !sessions.contains_key(&id)) {
+            target.set(None);
+        }
+    });
+
+    let mut send = move || {
+        let text = input();
+        let Some(handle) = target().and_then(|id| sessions.read().get(&id).cloned()) else {
+            error!("Choose a port to send to");
+            return;
+        };
+        let payload = match checksum() {
+            Some(checksum) => checksum.append(text.as_bytes()),
+            None => text.as_bytes().to_vec(),
+        };
+        let payload = framing.read().encode(&payload);
+        let message = Message::new(now.call(()), Direction::Out, payload);
+        match handle.write(message) {
+            Ok(()) => input.set(String::new()),
+            Err(e) => error!("{}", e),
+        }
+    };
+
+    rsx!(
+        document::Link { rel: "stylesheet", href: TERMINAL_CSS }
+
+        div { class: "terminal-view",
+            div { class: "terminal-toolbar",
+                h4 { "Terminal" }
+                if !follow() {
+                    button { onclick: move |_| follow.set(true), "Paused - jump to latest" }
+                }
+                button { onclick: move |_| history.write().clear(), "Clear" }
+            }
+            div {
+                class: "terminal-log",
+                onmounted: move |event| log_element.set(Some(event.data())),
+                onscroll,
+                {
+                    history.read().iter().map(|(seq, id, event)| {
+                        let port = port_list
+                            .read()
+                            .get(id)
+                            .map(|info| info.port.clone())
+                            .unwrap_or_default();
+                        render_entry(*seq, &port, event)
+                    })
+                }
+            }
+            div { class: "terminal-input",
+                select {
+                    value: target().map(|id| id.to_string()).unwrap_or_default(),
+                    onchange: move |event| target.set(event.value().parse().ok()),
+                    option { value: "", "Choose a port" }
+                    {
+                        port_list.read().iter().map(|(id, info)| {
+                            let id_str = id.to_string();
+                            let name = info.port.clone();
+                            rsx!(option { key: "{id_str}", value: "{id_str}", "{name}" })
+                        })
+                    }
+                }
+                input {
+                    value: "{input}",
+                    placeholder: "Send...",
+                    oninput: move |event| input.set(event.value()),
+                    onkeydown: move |event| {
+                        if event.key() == Key::Enter {
+                            send();
+                        }
+                    },
+                }
+                button { disabled: target().is_none(), onclick: move |_| send(), "Send" }
+            }
+        }
+    )
+}
+
+fn render_entry(seq: u64, port: &str, event: &SessionEvent) -> Element {
+    match event {
+        SessionEvent::Message(message) => {
+            let ts = format_timestamp(message.timestamp());
+            let (class, arrow) = match message.direction() {
+                Direction::In => ("terminal-line in", "<<"),
+                Direction::Out => ("terminal-line out", ">>"),
+            };
+            let text = message.text().trim_end_matches(['\r', '\n']).to_string();
+            rsx!(
+                div { key: "{seq}", class: class,
+                    span { class: "ts", "{ts}" }
+                    span { class: "dir", "{port} {arrow}" }
+                    span { class: "text", "{text}" }
+                }
+            )
+        }
+        SessionEvent::Error(e) => rsx!(
+            div { key: "{seq}", class: "terminal-line system", "{port}: {e}" }
+        ),
+        SessionEvent::Disconnected => rsx!(
+            div { key: "{seq}", class: "terminal-line system", "{port}: disconnected, reconnecting..." }
+        ),
+        SessionEvent::Reconnected(info) => rsx!(
+            div { key: "{seq}", class: "terminal-line system", "{port}: reconnected on {info.port}" }
+        ),
+        SessionEvent::Closed => rsx!(
+            div { key: "{seq}", class: "terminal-line system", "{port}: closed" }
+        ),
+    }
+}
+
+/// Format a Unix timestamp as `HH:MM:SS.mmm` (UTC)
+fn format_timestamp(timestamp: Timestamp) -> String {
+    let millis = timestamp.as_millis();
+    let seconds = millis / 1000;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        (seconds / 3600) % 24,
+        (seconds / 60) % 60,
+        seconds % 60,
+        millis % 1000
+    )
+}
@@ -1,10 +1,13 @@
 use dioxus::prelude::*;
 
-use crate::components::{ConnectionBar, PortList, RequestPort, SettingsPanel};
+use crate::components::{
+    ConnectionBar, Graph, ModbusPanel, Notifications, PortList, RequestPort, SettingsPanel,
+    Terminal,
+};
 
 #[allow(non_snake_case)]
 #[component]
-pub fn Layout() -> Element {
+pub fn Layout(children: Element) -> Element {
     rsx!(
         div { class: "app-layout",
             // Top actions slot: platforms can position a button over this area
@@ -18,22 +21,21 @@ pub fn Layout() -> Element {
             // Left sidebar
             aside { class: "sidebar",
                 ConnectionBar {}
+                Notifications {}
                 PortList {}
                 RequestPort {}
                 SettingsPanel {}
+                ModbusPanel {}
+                {children}
             }
 
-            // Main content area: top graph placeholder and bottom console
+            // Main content area: top graph and bottom console
             main { class: "main-area",
                 section { class: "graph-area",
-                    h3 { "Graph" }
-                    div { class: "graph-placeholder", "(graph placeholder)" }
+                    Graph {}
                 }
                 section { class: "console-area",
-                    div { class: "terminal-view",
-                        h4 { "Terminal" }
-                        p { "(terminal view skeleton)"}
-                    }
+                    Terminal {}
                 }
             }
         }
@@ -4,7 +4,9 @@ mod app;
 mod components;
 mod hero;
 mod layout;
+mod plot_context;
 mod serial_context;
 
 pub use app::App;
 pub use hero::Hero;
+pub use serial_context::SerialContext;
@@ -0,0 +1,22 @@
+use dioxus::prelude::*;
+
+use project_core::{data::PointBuffer, MatchMode, Parser, Pipeline};
+
+/// Shared plotting state: the parsers applied to each incoming message and
+/// the points they produced
+#[derive(Clone, Copy, PartialEq)]
+pub struct PlotContext {
+    pub parsers: Signal<Pipeline>,
+    pub points: Signal<PointBuffer>,
+}
+
+impl PlotContext {
+    /// Parsers used until the user configures some: `name: 1.5` /
+    /// `name=1.5`, any number of times per line
+    pub fn default_parsers() -> Pipeline {
+        let parser = Parser::new(r"(\w+)\s*[:=]\s*(-?\d+(?:\.\d+)?)", "$1", "$2")
+            .expect("default parser configuration is valid")
+            .with_match_mode(MatchMode::All);
+        Pipeline::new().with(Box::new(parser))
+    }
+}
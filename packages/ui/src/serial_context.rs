@@ -1,14 +1,84 @@
-use dioxus::prelude::*;
-use std::collections::HashMap;
+use dioxus::{logger::tracing::warn, prelude::*};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use tokio::sync::broadcast::error::RecvError;
 use uuid::Uuid;
 
 use project_core::{
+    data::Timestamp,
+    framing::{Checksum, Framing},
+    modbus::PollItem,
     serial::{PortConfig, PortInfo},
+    session::{SessionEvent, SessionHandle},
     Result as CoreResult,
 };
 
 #[derive(Clone, PartialEq)]
 pub struct SerialContext {
     pub request_port: Callback<(PortInfo, PortConfig), CoreResult<()>>,
+    /// Whether `request_port` accepts `PortType::Network` ports
+    pub network_ports: bool,
     pub port_list: ReadSignal<HashMap<Uuid, PortInfo>>,
+    /// Ports reported by `SerialPort::list_ports()`, opened or not
+    pub available_ports: ReadSignal<Vec<PortInfo>>,
+    /// Re-run port enumeration
+    pub refresh_ports: Callback<()>,
+    /// Open ports whose device was removed, until dismissed
+    pub unplugged: Signal<Vec<PortInfo>>,
+    /// Running sessions, keyed like `port_list`
+    pub sessions: ReadSignal<HashMap<Uuid, SessionHandle>>,
+    /// Current time from the platform `TimeSource`
+    pub now: Callback<(), Timestamp>,
+    /// Framing for ports opened from now on, also used to encode writes
+    pub framing: Signal<Framing>,
+    /// Checksum verified on incoming frames and appended to writes
+    pub checksum: Signal<Option<Checksum>>,
+    /// Reopen ports opened from now on when their device disappears
+    pub auto_reconnect: Signal<bool>,
+    /// Open a port as a Modbus RTU master and plot the polled values,
+    /// replacing any poll already running
+    pub start_modbus: Callback<(PortInfo, PortConfig, Vec<PollItem>), CoreResult<()>>,
+    /// Stop the running Modbus poll after its current request
+    pub stop_modbus: Callback<()>,
+    pub modbus_running: ReadSignal<bool>,
+}
+
+/// Subscribe to every session in `SerialContext`, including sessions opened
+/// later, and call `handler` with each event. The subscription for a session
+/// ends after its `SessionEvent::Closed`.
+pub fn use_session_events(handler: impl FnMut(Uuid, SessionEvent) + 'static) {
+    let sessions = use_context::<SerialContext>().sessions;
+    let handler = use_hook(|| Rc::new(RefCell::new(handler)));
+    let subscribed = use_hook(|| Rc::new(RefCell::new(HashSet::<Uuid>::new())));
+
+    use_effect(move || {
+        for (id, handle) in sessions.read().iter() {
+            if !subscribed.borrow_mut().insert(*id) {
+                continue;
+            }
+            let id = *id;
+            let mut events = handle.subscribe();
+            let handler = handler.clone();
+            let subscribed = subscribed.clone();
+            spawn(async move {
+                loop {
+                    match events.recv().await {
+                        Ok(event) => {
+                            let closed = event == SessionEvent::Closed;
+                            (handler.borrow_mut())(id, event);
+                            if closed {
+                                break;
+                            }
+                        }
+                        Err(RecvError::Lagged(skipped)) => {
+                            warn!("Dropped {} events from session {}", skipped, id);
+                        }
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+                subscribed.borrow_mut().remove(&id);
+            });
+        }
+    });
 }
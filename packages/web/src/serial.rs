@@ -1,20 +1,26 @@
 mod helper;
 
+use std::cell::{Cell, RefCell};
+use std::ops::ControlFlow;
+use std::rc::Rc;
+
 use async_trait::async_trait;
 use project_core::serial::SerialPort;
+use tokio::sync::mpsc;
+use wasm_bindgen::closure::Closure;
 use wasm_bindgen::JsCast;
 use wasm_bindgen::JsValue;
 use wasm_bindgen_futures::JsFuture;
 use web_sys;
 
-use js_sys::{Date, Function, Reflect, Uint8Array};
+use js_sys::{Array, Date, Function, Reflect, Uint8Array};
 use project_core::{
     data::{Direction, Message, Timestamp},
-    serial::{PortConfig, PortInfo, SerialPortConfig},
+    serial::{watch::PortEvent, FlowControl, Parity, PortConfig, PortInfo, SerialPortConfig},
     Result as CoreResult,
 };
 
-use helper::js_port_to_port_info;
+use helper::{js_port_to_port_info, port_id};
 
 /// Simple WebSerial that owns an optional `web_sys::SerialPort` instance and
 /// implements the `project_core::SerialPort` trait.
@@ -23,7 +29,10 @@ pub struct WebSerialPort {
     port: web_sys::SerialPort,
     info: PortInfo,
     config: PortConfig,
-    is_open: bool,
+    // Shared between clones so a session can read on one clone while
+    // writing and closing through another.
+    is_open: Rc<Cell<bool>>,
+    reader: Rc<RefCell<Option<JsValue>>>,
 }
 
 impl WebSerialPort {
@@ -42,19 +51,48 @@ impl WebSerialPort {
             project_core::Error::OpenError("Failed to cast to SerialPort".to_string())
         })?;
 
+        Ok(Self::from_port(port, config).await)
+    }
+
+    async fn from_port(port: web_sys::SerialPort, config: PortConfig) -> Self {
         // Try to extract richer `PortInfo` metadata from the selected port.
         let info = js_port_to_port_info(&port).await;
 
-        Ok(Self {
+        Self {
             port,
             info,
             config,
-            is_open: false,
-        })
+            is_open: Rc::new(Cell::new(false)),
+            reader: Rc::new(RefCell::new(None)),
+        }
     }
 
-    async fn read(&mut self) -> CoreResult<Uint8Array> {
-        use wasm_bindgen_futures::JsFuture;
+    /// Ports the user has already granted this origin access to
+    async fn granted_ports() -> CoreResult<Vec<web_sys::SerialPort>> {
+        let window = web_sys::window()
+            .ok_or_else(|| project_core::Error::DeviceNotFound("No window object".to_string()))?;
+        let serial = window.navigator().serial();
+
+        let ports_js = JsFuture::from(serial.get_ports()).await.map_err(|_| {
+            project_core::Error::SerialError("navigator.serial.getPorts failed".to_string())
+        })?;
+        let ports: Array = ports_js.dyn_into().map_err(|_| {
+            project_core::Error::SerialError("getPorts did not return an array".to_string())
+        })?;
+
+        Ok(ports
+            .iter()
+            .filter_map(|port_js| port_js.dyn_into::<web_sys::SerialPort>().ok())
+            .collect())
+    }
+
+    /// Return the reader locked on `port.readable`, creating it on first use.
+    /// The reader is kept across reads so an in-flight `read()` is never
+    /// orphaned; `close()` cancels and releases it.
+    fn reader(&self) -> CoreResult<JsValue> {
+        if let Some(reader) = self.reader.borrow().as_ref() {
+            return Ok(reader.clone());
+        }
 
         // Use JS reflection to access the readable stream and its reader so
         // we don't depend on specific web-sys bindings which may vary.
@@ -81,6 +119,15 @@ impl WebSerialPort {
             .call0(&readable)
             .map_err(|_| project_core::Error::ReadError("Failed to get reader".to_string()))?;
 
+        *self.reader.borrow_mut() = Some(reader.clone());
+        Ok(reader)
+    }
+
+    async fn read(&mut self) -> CoreResult<Uint8Array> {
+        use wasm_bindgen_futures::JsFuture;
+
+        let reader = self.reader()?;
+
         // call reader.read(&reader) -> Promise
         let read: Function = Reflect::get(&reader, &JsValue::from_str("read"))
             .map_err(|_| project_core::Error::ReadError("Reader.read not available".to_string()))?
@@ -97,6 +144,16 @@ impl WebSerialPort {
         let result = JsFuture::from(promise).await.map_err(|_| {
             project_core::Error::ReadError("reader.read promise failed".to_string())
         })?;
+        // result.done is set once the stream has been cancelled or closed
+        let done = Reflect::get(&result, &JsValue::from_str("done"))
+            .ok()
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        if done {
+            return Err(project_core::Error::ReadError(
+                "Readable stream closed".to_string(),
+            ));
+        }
         // result.value is the Uint8Array (or undefined)
         let value = Reflect::get(&result, &JsValue::from_str("value")).unwrap_or(JsValue::NULL);
         if value.is_null() || value.is_undefined() {
@@ -108,6 +165,30 @@ impl WebSerialPort {
         Ok(array)
     }
 
+    /// Cancel any pending read and release the reader lock so the port can
+    /// be closed.
+    async fn release_reader(&self) {
+        let Some(reader) = self.reader.borrow_mut().take() else {
+            return;
+        };
+        if let Ok(cancel) = Reflect::get(&reader, &JsValue::from_str("cancel")) {
+            if cancel.is_function() {
+                let f: Function = cancel.unchecked_into();
+                if let Ok(promise) = f.call0(&reader) {
+                    if let Ok(promise) = promise.dyn_into::<js_sys::Promise>() {
+                        let _ = JsFuture::from(promise).await;
+                    }
+                }
+            }
+        }
+        if let Ok(release) = Reflect::get(&reader, &JsValue::from_str("releaseLock")) {
+            if release.is_function() {
+                let f: Function = release.unchecked_into();
+                let _ = f.call0(&reader);
+            }
+        }
+    }
+
     async fn write(&mut self, array: &Uint8Array) -> CoreResult<()> {
         use wasm_bindgen_futures::JsFuture;
 
@@ -181,61 +262,143 @@ impl SerialPortConfig for WebSerialPort {
 
 #[async_trait(?Send)]
 impl SerialPort for WebSerialPort {
-    /// Request a port from the user and return a `WebSerialPort` owning that port.
-    /// The first argument `info` will not be used, as the user selects the port.
-    async fn request_port(_: PortInfo, config: PortConfig) -> CoreResult<Self> {
+    /// List ports the user has already granted this origin access to.
+    async fn list_ports() -> CoreResult<Vec<PortInfo>> {
+        let ports = Self::granted_ports().await?;
+        let mut infos = Vec::with_capacity(ports.len());
+        for port in &ports {
+            infos.push(js_port_to_port_info(port).await);
+        }
+        Ok(infos)
+    }
+
+    /// Listens for `connect` and `disconnect` on `navigator.serial`, which
+    /// fire for ports this page has been granted
+    async fn watch_ports(on_event: &mut dyn FnMut(PortEvent) -> ControlFlow<()>) -> CoreResult<()> {
+        let window = web_sys::window()
+            .ok_or_else(|| project_core::Error::DeviceNotFound("No window object".to_string()))?;
+        let serial = window.navigator().serial();
+
+        let (tx, mut rx) = mpsc::unbounded_channel::<(bool, web_sys::SerialPort)>();
+        let listener = |added: bool| {
+            let tx = tx.clone();
+            Closure::<dyn FnMut(web_sys::Event)>::new(move |event: web_sys::Event| {
+                // The event is dispatched at the port that came or went
+                if let Some(port) = event
+                    .target()
+                    .and_then(|target| target.dyn_into::<web_sys::SerialPort>().ok())
+                {
+                    let _ = tx.send((added, port));
+                }
+            })
+        };
+        let listeners = [("connect", listener(true)), ("disconnect", listener(false))];
+        for (name, listener) in &listeners {
+            serial
+                .add_event_listener_with_callback(name, listener.as_ref().unchecked_ref())
+                .map_err(|_| {
+                    project_core::Error::SerialError(format!("Failed to listen for {}", name))
+                })?;
+        }
+
+        while let Some((added, port)) = rx.recv().await {
+            let info = js_port_to_port_info(&port).await;
+            let event = if added {
+                PortEvent::Added(info)
+            } else {
+                PortEvent::Removed(info)
+            };
+            if on_event(event).is_break() {
+                break;
+            }
+        }
+
+        for (name, listener) in &listeners {
+            let _ =
+                serial.remove_event_listener_with_callback(name, listener.as_ref().unchecked_ref());
+        }
+        Ok(())
+    }
+
+    /// Return a `WebSerialPort` for the granted port `info` came from (by
+    /// `PortInfo::id`), or ask the user to choose one when there is none.
+    async fn request_port(info: PortInfo, config: PortConfig) -> CoreResult<Self> {
+        if let Some(id) = info.id {
+            let granted = Self::granted_ports().await?;
+            if let Some(port) = granted.into_iter().find(|port| port_id(port) == id) {
+                return Ok(Self::from_port(port, config).await);
+            }
+        }
         Self::request_port(config).await
     }
 
     async fn open(&mut self) -> CoreResult<()> {
+        self.config.validate()?;
+
         let options = web_sys::SerialOptions::new(self.config.baud_rate);
         options.set_data_bits(self.config.data_bits);
         options.set_stop_bits(self.config.stop_bits);
+        options.set_parity(match self.config.parity {
+            Parity::None => web_sys::ParityType::None,
+            Parity::Odd => web_sys::ParityType::Odd,
+            Parity::Even => web_sys::ParityType::Even,
+        });
+        options.set_flow_control(match self.config.flow_control {
+            FlowControl::None => web_sys::FlowControlType::None,
+            FlowControl::Hardware => web_sys::FlowControlType::Hardware,
+            // Web Serial only exposes "none" and "hardware"
+            FlowControl::Software => {
+                return Err(project_core::Error::ConfigError(
+                    "Software flow control is not supported by Web Serial".to_string(),
+                ))
+            }
+        });
+        // `validate()` caps the buffer below the Web Serial limit, so this fits
+        options.set_buffer_size(self.config.buffer_size as u32);
+        // Web Serial has no read timeout; `read_timeout` is ignored here.
 
         let promise = self.port.open(&options);
         JsFuture::from(promise)
             .await
             .map_err(|_| project_core::Error::OpenError("Failed to open port".to_string()))?;
-        self.is_open = true;
+        self.is_open.set(true);
         Ok(())
     }
 
     async fn close(&mut self) -> CoreResult<()> {
+        self.release_reader().await;
         let promise = self.port.close();
         JsFuture::from(promise)
             .await
             .map_err(|_| project_core::Error::OpenError("Failed to close port".to_string()))?;
-        self.is_open = false;
+        self.is_open.set(false);
         Ok(())
     }
 
     async fn read(&mut self) -> CoreResult<Message> {
-        if !self.is_open {
+        if !self.is_open.get() {
             return Err(project_core::Error::ReadError(
                 "Port is not open".to_string(),
             ));
         }
         let array = self.read().await?;
-        let text = String::from_utf8_lossy(&array.to_vec()).to_string();
         let timestamp = Timestamp(Date::now() as u64);
-        Ok(Message::new(timestamp, Direction::In, text))
+        Ok(Message::new(timestamp, Direction::In, array.to_vec()))
     }
 
     async fn write(&mut self, message: Message) -> CoreResult<()> {
-        if !self.is_open {
+        if !self.is_open.get() {
             return Err(project_core::Error::WriteError(
                 "Port is not open".to_string(),
             ));
         }
-        // Convert message text to bytes
-        let bytes = message.text().as_bytes();
-        let array = Uint8Array::from(bytes);
+        let array = Uint8Array::from(message.bytes());
 
         self.write(&array).await
     }
 
     fn is_open(&self) -> bool {
-        self.is_open
+        self.is_open.get()
     }
 
     /// Web Serial doesn't expose a flush primitive; noop for now.
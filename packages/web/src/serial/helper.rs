@@ -1,13 +1,41 @@
+use std::cell::Cell;
+
 use dioxus::logger::tracing::warn;
-use js_sys::{Function, Reflect};
+use js_sys::{Function, Object, Reflect, WeakMap};
 use wasm_bindgen::{JsCast, JsValue};
 
 use project_core::serial::{PortInfo, PortType};
 
+thread_local! {
+    /// Id given to each `SerialPort` object seen so far. The browser hands
+    /// out the same object for a port every time, so its id stays stable.
+    static PORT_IDS: WeakMap = WeakMap::new();
+    static NEXT_PORT_ID: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Id telling this port apart from others; every Web Serial port has the
+/// same name
+pub fn port_id(port: &web_sys::SerialPort) -> u64 {
+    let key: &Object = port.as_ref();
+    PORT_IDS.with(|ids| {
+        if let Some(id) = ids.get(key).as_f64() {
+            return id as u64;
+        }
+        let id = NEXT_PORT_ID.replace(NEXT_PORT_ID.get() + 1);
+        ids.set(key, &JsValue::from_f64(id as f64));
+        id
+    })
+}
+
+/// `PortInfo` for a `web_sys::SerialPort`, carrying its `port_id`
+pub async fn js_port_to_port_info(port: &web_sys::SerialPort) -> PortInfo {
+    describe_port(port).await.with_id(port_id(port))
+}
+
 // Try to extract `PortInfo` from a `web_sys::SerialPort` using the
 // Web Serial `getInfo()` method when available. This is an async helper
 // because `getInfo()` returns a Promise.
-pub async fn js_port_to_port_info(port: &web_sys::SerialPort) -> PortInfo {
+async fn describe_port(port: &web_sys::SerialPort) -> PortInfo {
     let port_js = JsValue::from(port);
     let info_js = {
         let Ok(get_info_js) = Reflect::get(&port_js, &JsValue::from_str("getInfo")) else {
@@ -1,4 +1,9 @@
-use js_sys::Date;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+use js_sys::{Date, Promise};
+use wasm_bindgen_futures::JsFuture;
 
 use project_core::{data::Timestamp, TimeSource};
 
@@ -9,4 +14,17 @@ impl TimeSource for SystemTimeSource {
     fn now_millis() -> Timestamp {
         Timestamp(Date::now() as u64)
     }
+
+    fn sleep(duration: Duration) -> Pin<Box<dyn Future<Output = ()>>> {
+        let millis = duration.as_millis().min(i32::MAX as u128) as i32;
+        let promise = Promise::new(&mut |resolve, _| {
+            if let Some(window) = web_sys::window() {
+                let _ =
+                    window.set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, millis);
+            }
+        });
+        Box::pin(async move {
+            let _ = JsFuture::from(promise).await;
+        })
+    }
 }